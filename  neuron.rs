@@ -1,113 +1,750 @@
 // neuron.rs
-use crate::activation::Activation;
-use crate::database::NeuronDb;
+use crate::activation::{self, Activation};
+use crate::database::{DatabaseError, NeuronBatch, NeuronDb};
 use crate::extensions::{EyeExt, WebhookStreamExt, MessengerInExt, MessengerOutExt};
+use crate::input_schema::InputSchema;
+use crate::learning::{LearningRule, LearningRuleState, LocalLearning};
+use crate::metrics_exporter::NeuronSnapshot;
+use crate::neuron_metrics::{NeuronMetrics, NeuronTotals};
+use crate::neuron_state::{ExtensionTokens, ForwardPass, NeuronState, Parameters};
+use crate::neuromodulation::{ModulationBinding, ModulationTarget, Neuromodulation, NeuromodulatorLevels};
+use crate::optimizer::{Optimizer, OptimizerState, Sgd};
+use crate::reporter::Reporter;
+use crate::shutdown::{Drain, DrainGuard};
+use crate::spiking::{self, LifConfig, MembraneState, Spiking};
+use crate::stdp::{Stdp, StdpConfig, StdpTraces};
+use crate::synapse::{SynapseConfig, SynapseError, Synapses};
+use crate::telemetry;
 use crate::proto::neuron_service_server::NeuronService;
-use crate::proto::supervisor_client::SupervisorClient;
-use crate::proto::{InputSignal, OutputSignal, SupervisorRequest};
+use crate::proto::{
+    BackwardRequest, BackwardResponse, InputBatch, InputSignal, LearningState, LearningToggle,
+    NeuronParameters, NeuronRegistration, OutputBatch, OutputSignal, ParametersRequest, SpikeEvent, SpikeSubscription,
+    SynapticAck, SynapticInput, WeightUpdate,
+};
 use crate::weight_init::WeightInitializer;
 use std::collections::HashMap;
-use std::time::Instant;
-use tokio::sync::mpsc;
-use tonic::{Request, Response, Status};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc, watch, RwLockReadGuard};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::Span;
 
 pub struct Neuron {
     id: String,
-    weights: Vec<f32>,
+    input_schema: InputSchema,
+    state: NeuronState,
+    metrics: NeuronMetrics,
+    last_report: Mutex<HashMap<String, f64>>,
     activation: Box<dyn Activation>,
+    learning: Option<LocalLearning>,
+    optimizer: Mutex<Box<dyn Optimizer>>,
+    spiking: Option<Spiking>,
+    stdp: Option<Stdp>,
+    neuromodulation: Option<Neuromodulation>,
+    synapses: Option<Synapses>,
     db: NeuronDb,
+    reporter: Reporter,
+    drain: Arc<Drain>,
     eye_ext: Option<EyeExt>,
     webhook_ext: Option<WebhookStreamExt>,
     messenger_in_ext: Option<MessengerInExt>,
     messenger_out_ext: Option<MessengerOutExt>,
-    extension_receiver: Option<mpsc::Receiver<Vec<f32>>>,
+}
+
+const DEFAULT_LEARNING_RATE: f32 = 0.01;
+
+#[derive(Error, Debug)]
+pub enum NeuronError {
+    #[error("Database error: {0}")]
+    Database(#[from] DatabaseError),
+    #[error("Stored weights have {stored} inputs but the input schema expects {expected}")]
+    DimensionMismatch { stored: usize, expected: usize },
+    #[error("Unknown stored activation: {0}")]
+    UnknownActivation(String),
+    #[error("Corrupt persisted state: {0}")]
+    PersistedState(#[from] serde_json::Error),
+    #[error("STDP requires the neuron to be in spiking mode")]
+    StdpRequiresSpiking,
+    #[error("STDP weight bounds are empty: w_min {w_min} exceeds w_max {w_max}")]
+    InvalidStdpBounds { w_min: f32, w_max: f32 },
+    #[error("Synapse error: {0}")]
+    Synapse(#[from] SynapseError),
+}
+
+/// What `Neuron::open` does when the stored weights don't match the input schema.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizePolicy {
+    /// Refuse to start.
+    Refuse,
+    /// Keep the overlapping weights and initialize any added ones.
+    Resize,
+    /// Discard the stored weights and bias and initialize from scratch.
+    Reinitialize,
 }
 
 impl Neuron {
     pub fn new(
         id: String,
-        num_inputs: usize,
+        input_schema: InputSchema,
         activation: Box<dyn Activation>,
         weight_initializer: &dyn WeightInitializer,
         eye_ext: Option<EyeExt>,
         webhook_ext: Option<WebhookStreamExt>,
         messenger_in_ext: Option<MessengerInExt>,
         messenger_out_ext: Option<MessengerOutExt>,
-        extension_receiver: Option<mpsc::Receiver<Vec<f32>>>,
+        extension_receiver: Option<mpsc::Receiver<ExtensionTokens>>,
     ) -> Self {
-        let mut weights = vec![0.0; num_inputs];
-        weight_initializer.initialize(&mut weights);
-        let db = NeuronDb::new(&id).expect("Failed to create neuron database");
-        Self {
+        Self::open(
             id,
-            weights,
+            input_schema,
             activation,
+            weight_initializer,
+            ResizePolicy::Refuse,
+            eye_ext,
+            webhook_ext,
+            messenger_in_ext,
+            messenger_out_ext,
+            extension_receiver,
+        )
+        .expect("Failed to open neuron")
+    }
+
+    /// Opens the neuron's database and restores its weights, bias and activation
+    /// from it, falling back to `weight_initializer` and `activation` for a fresh neuron.
+    pub fn open(
+        id: String,
+        input_schema: InputSchema,
+        activation: Box<dyn Activation>,
+        weight_initializer: &dyn WeightInitializer,
+        resize_policy: ResizePolicy,
+        eye_ext: Option<EyeExt>,
+        webhook_ext: Option<WebhookStreamExt>,
+        messenger_in_ext: Option<MessengerInExt>,
+        messenger_out_ext: Option<MessengerOutExt>,
+        extension_receiver: Option<mpsc::Receiver<ExtensionTokens>>,
+    ) -> Result<Self, NeuronError> {
+        let db = NeuronDb::new(&id)?;
+        Self::open_with_db(
+            id,
             db,
+            input_schema,
+            activation,
+            weight_initializer,
+            resize_policy,
             eye_ext,
             webhook_ext,
             messenger_in_ext,
             messenger_out_ext,
             extension_receiver,
-        }
+        )
     }
 
-    async fn report_status(&self, status: String) {
-        let mut client = SupervisorClient::connect("http://[::1]:50052")
-            .await
-            .expect("Failed to connect to supervisor");
-        let request = Request::new(SupervisorRequest {
+    /// Like `open`, but on an already opened (typically shared and scoped) database.
+    pub fn open_with_db(
+        id: String,
+        db: NeuronDb,
+        input_schema: InputSchema,
+        activation: Box<dyn Activation>,
+        weight_initializer: &dyn WeightInitializer,
+        resize_policy: ResizePolicy,
+        eye_ext: Option<EyeExt>,
+        webhook_ext: Option<WebhookStreamExt>,
+        messenger_in_ext: Option<MessengerInExt>,
+        messenger_out_ext: Option<MessengerOutExt>,
+        extension_receiver: Option<mpsc::Receiver<ExtensionTokens>>,
+    ) -> Result<Self, NeuronError> {
+        let parameters = Self::restore_parameters(&db, &input_schema, weight_initializer, resize_policy)?;
+        let activation = Self::restore_activation(&db, activation)?;
+        let optimizer = Self::restore_optimizer(&db, Box::new(Sgd::new(DEFAULT_LEARNING_RATE, 0.0)))?;
+        let state = NeuronState::new(parameters, extension_receiver);
+        Ok(Self {
+            id,
+            input_schema,
+            state,
+            metrics: NeuronMetrics::new(),
+            last_report: Mutex::new(HashMap::new()),
+            activation,
+            learning: None,
+            optimizer: Mutex::new(optimizer),
+            spiking: None,
+            stdp: None,
+            neuromodulation: None,
+            synapses: None,
+            db,
+            reporter: Reporter::from_env(),
+            drain: Arc::new(Drain::new()),
+            eye_ext,
+            webhook_ext,
+            messenger_in_ext,
+            messenger_out_ext,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Describes this neuron to the supervisor, which can reach it at `address`.
+    pub fn registration(&self, address: String) -> NeuronRegistration {
+        let extensions = [
+            ("eye", self.eye_ext.is_some()),
+            ("webhook", self.webhook_ext.is_some()),
+            ("messenger_in", self.messenger_in_ext.is_some()),
+            ("messenger_out", self.messenger_out_ext.is_some()),
+        ];
+        NeuronRegistration {
             neuron_id: self.id.clone(),
-            status,
-            metrics: "".to_string(),
-            command: "".to_string(),
-            args: vec![],
+            address,
+            num_inputs: self.input_schema.size() as u32,
+            activation: self.activation.name().to_string(),
+            extensions: extensions
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }
+    }
+
+    /// Reports to the supervisor through `reporter` instead of a client of its own.
+    pub fn with_reporter(mut self, reporter: Reporter) -> Self {
+        self.reporter = reporter;
+        self
+    }
+
+    /// Enables a local learning rule applied after every `ProcessInput`,
+    /// resuming its persisted state if it is the rule that stored it.
+    pub fn with_learning_rule(
+        mut self,
+        rule: Box<dyn LearningRule>,
+        learning_rate: f32,
+    ) -> Result<Self, NeuronError> {
+        let learning = LocalLearning::new(rule, learning_rate);
+        if let Some(bytes) = self.db.get(b"learning_state")? {
+            let state: LearningRuleState = serde_json::from_slice(&bytes)?;
+            if state.name == learning.rule_name() {
+                learning.load_state(state);
+            } else {
+                log::warn!(
+                    "Ignoring stored {} learning state for configured {} rule",
+                    state.name,
+                    learning.rule_name()
+                );
+            }
+        }
+        self.learning = Some(learning);
+        Ok(self)
+    }
+
+    /// Replaces the optimizer used by `Backward`, resuming its persisted
+    /// moment buffers if the stored state belongs to the same optimizer.
+    pub fn with_optimizer(self, optimizer: Box<dyn Optimizer>) -> Result<Self, NeuronError> {
+        let optimizer = Self::restore_optimizer(&self.db, optimizer)?;
+        *self.optimizer.lock().expect("Optimizer lock poisoned") = optimizer;
+        Ok(self)
+    }
+
+    /// Switches the neuron to leaky integrate-and-fire mode, resuming the
+    /// persisted membrane state if there is one.
+    pub fn with_spiking(mut self, config: LifConfig) -> Result<Self, NeuronError> {
+        let membrane = match self.db.get(b"membrane_state")? {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => MembraneState::resting(&config),
+        };
+        self.spiking = Some(Spiking::new(config, membrane));
+        Ok(self)
+    }
+
+    /// Enables STDP on a spiking neuron, resuming the persisted synaptic traces.
+    pub fn with_stdp(mut self, config: StdpConfig) -> Result<Self, NeuronError> {
+        if self.spiking.is_none() {
+            return Err(NeuronError::StdpRequiresSpiking);
+        }
+        if config.w_min > config.w_max {
+            return Err(NeuronError::InvalidStdpBounds {
+                w_min: config.w_min,
+                w_max: config.w_max,
+            });
+        }
+        let traces = match self.db.get(b"stdp_traces")? {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => StdpTraces::default(),
+        };
+        self.stdp = Some(Stdp::new(config, traces));
+        Ok(self)
+    }
+
+    /// Scales learning rate, activation gain or firing threshold by the
+    /// neuromodulator levels published on `levels`.
+    pub fn with_neuromodulation(
+        mut self,
+        bindings: Vec<ModulationBinding>,
+        levels: watch::Receiver<NeuromodulatorLevels>,
+    ) -> Self {
+        self.neuromodulation = Some(Neuromodulation::new(bindings, levels));
+        self
+    }
+
+    /// Connects the neuron into a network: it fires once per wave after every
+    /// upstream neuron has delivered, and forwards its output downstream.
+    pub fn with_synapses(mut self, config: SynapseConfig) -> Result<Self, NeuronError> {
+        self.synapses = Some(Synapses::connect(config, self.input_schema.core_size())?);
+        Ok(self)
+    }
+
+    fn restore_parameters(
+        db: &NeuronDb,
+        input_schema: &InputSchema,
+        weight_initializer: &dyn WeightInitializer,
+        resize_policy: ResizePolicy,
+    ) -> Result<Parameters, NeuronError> {
+        let expected = input_schema.size();
+        let mut initialized = vec![0.0; expected];
+        weight_initializer.initialize(&mut initialized);
+
+        let stored = match db.get_f32s(b"weights")? {
+            Some(stored) => stored,
+            None => {
+                log::info!("No stored weights found, initializing {} weights", expected);
+                return Ok(Parameters::new(initialized, 0.0));
+            }
+        };
+
+        let weights = if stored.len() == expected {
+            stored
+        } else {
+            match resize_policy {
+                ResizePolicy::Refuse => {
+                    return Err(NeuronError::DimensionMismatch {
+                        stored: stored.len(),
+                        expected,
+                    })
+                }
+                ResizePolicy::Resize => {
+                    log::warn!("Resizing stored weights from {} to {} inputs", stored.len(), expected);
+                    let kept = stored.len().min(expected);
+                    initialized[..kept].copy_from_slice(&stored[..kept]);
+                    initialized
+                }
+                ResizePolicy::Reinitialize => {
+                    log::warn!("Discarding {} stored weights, reinitializing {}", stored.len(), expected);
+                    return Ok(Parameters::new(initialized, 0.0));
+                }
+            }
+        };
+
+        let bias = db
+            .get_f32s(b"bias")?
+            .and_then(|bias| bias.first().copied())
+            .unwrap_or(0.0);
+        let version = db.get_u64(b"weights_version")?.unwrap_or(0);
+        log::info!("Restored {} weights at version {}", weights.len(), version);
+        Ok(Parameters {
+            weights,
+            bias,
+            version,
+            updated_at_ms: 0,
+        })
+    }
+
+    fn restore_optimizer(
+        db: &NeuronDb,
+        mut optimizer: Box<dyn Optimizer>,
+    ) -> Result<Box<dyn Optimizer>, NeuronError> {
+        if let Some(bytes) = db.get(b"optimizer_state")? {
+            let state: OptimizerState = serde_json::from_slice(&bytes)?;
+            if state.name == optimizer.name() {
+                optimizer.load_state(state);
+            } else {
+                log::warn!(
+                    "Ignoring stored {} optimizer state for configured {} optimizer",
+                    state.name,
+                    optimizer.name()
+                );
+            }
+        }
+        Ok(optimizer)
+    }
+
+    fn restore_activation(
+        db: &NeuronDb,
+        activation: Box<dyn Activation>,
+    ) -> Result<Box<dyn Activation>, NeuronError> {
+        match db.get(b"activation_config")? {
+            Some(name) => {
+                let name = String::from_utf8_lossy(&name).into_owned();
+                activation::from_name(&name).ok_or(NeuronError::UnknownActivation(name))
+            }
+            None => {
+                db.put(b"activation_config", activation.name().as_bytes())?;
+                Ok(activation)
+            }
+        }
+    }
+
+    fn report_status(&self, status: String) {
+        self.reporter.report_status(&self.id, status);
+    }
+
+    fn report_metrics(&self, metrics: HashMap<String, f64>) {
+        self.reporter.report_metrics(&self.id, metrics);
+    }
+
+    /// Reports the metrics gathered since the previous report.
+    async fn report_window(&self) {
+        let parameters = self.state.parameters().await;
+        let metrics = self.metrics.take(NeuronTotals {
+            request_count: self.state.request_count(),
+            error_count: self.state.error_count(),
+            weight_norm: parameters.weight_norm(),
+            last_update_ms: parameters.updated_at_ms,
         });
-        client
-            .report_neuron_status(request)
-            .await
-            .expect("Failed to report neuron status");
+        drop(parameters);
+        *self.last_report.lock().expect("Metrics report lock poisoned") = metrics.clone();
+        self.report_metrics(metrics);
     }
 
-    async fn report_metrics(&self, metrics: HashMap<String, f64>) {
-        let mut client = SupervisorClient::connect("http://[::1]:50052")
-            .await
-            .expect("Failed to connect to supervisor");
-        let metrics_json = serde_json::to_string(&metrics).expect("Failed to serialize metrics");
-        let request = Request::new(SupervisorRequest {
+    /// The most recently reported metrics window, with live request and
+    /// error counts, for scrape-based monitoring.
+    pub fn metrics_snapshot(&self) -> NeuronSnapshot {
+        let mut metrics = self.last_report.lock().expect("Metrics report lock poisoned").clone();
+        metrics.insert("request_count".to_string(), self.state.request_count() as f64);
+        metrics.insert("error_count".to_string(), self.state.error_count() as f64);
+        NeuronSnapshot {
             neuron_id: self.id.clone(),
-            status: "".to_string(),
-            metrics: metrics_json,
-            command: "".to_string(),
-            args: vec![],
-        });
-        client
-            .report_neuron_metrics(request)
-            .await
-            .expect("Failed to report neuron metrics");
+            status: None,
+            metrics,
+        }
     }
 
-    fn calculate_metrics(&self, processing_time: f64) -> HashMap<String, f64> {
-        let mut metrics = HashMap::new();
-        metrics.insert("processing_time".to_string(), processing_time);
-        // Placeholder implementation. Replace with actual metric calculation logic.
-        metrics
+    /// Whether the neuron accepts inputs and its database still answers reads.
+    pub fn is_ready(&self) -> bool {
+        !self.drain.is_closed() && self.db.get(b"weights_version").is_ok()
     }
 
-    async fn process_extensions(&mut self) -> Result<Vec<f32>, Status> {
-        let mut input_tokens = Vec::new();
-        while let Some(tokens) = self
-            .extension_receiver
-            .as_mut()
-            .expect("Extension receiver not set")
-            .recv()
-            .await
-        {
-            input_tokens.extend(tokens);
+    /// Admits one input or update until the guard is dropped; fails once
+    /// `shutdown` has started.
+    pub(crate) fn admit(&self) -> Result<DrainGuard, Status> {
+        Drain::enter(&self.drain)
+            .ok_or_else(|| Status::unavailable(format!("Neuron {} is shutting down", self.id)))
+    }
+
+    /// Stops accepting inputs and updates, waits for the ones in flight, then
+    /// flushes the database and delivers a final metrics window to the
+    /// supervisor. Waits for the supervisor as long as it takes, so callers
+    /// bound it with a deadline.
+    pub async fn shutdown(&self) {
+        self.drain.close();
+        let in_flight = self.drain.in_flight();
+        if in_flight > 0 {
+            log::info!("Neuron {} draining {} inputs", self.id, in_flight);
+        }
+        self.drain.drained().await;
+        if let Err(e) = self.db.flush() {
+            log::error!("Failed to flush database of Neuron {}: {}", self.id, e);
+        }
+        self.report_window().await;
+        self.report_status("Stopped".to_string());
+        self.reporter.flush().await;
+        log::info!("Neuron {} shut down", self.id);
+    }
+
+    /// Reports `neuron`'s metrics to the supervisor every `interval` until the
+    /// neuron is dropped.
+    pub fn spawn_metrics_reporting(neuron: &Arc<Neuron>, interval: Duration) -> JoinHandle<()> {
+        let neuron = Arc::downgrade(neuron);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately.
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                match neuron.upgrade() {
+                    Some(neuron) => neuron.report_window().await,
+                    None => break,
+                }
+            }
+        })
+    }
+
+    /// Serves one `ProcessInputStream` call from a task that keeps `neuron`
+    /// alive until the client closes its side or an input fails.
+    fn spawn_input_stream(
+        neuron: Arc<Neuron>,
+        request: Request<Streaming<InputSignal>>,
+    ) -> ReceiverStream<Result<OutputSignal, Status>> {
+        let context = telemetry::server_context("process_input_stream", &request);
+        let mut inputs = request.into_inner();
+        let (sender, receiver) = mpsc::channel(32);
+        tokio::spawn(context.run(async move {
+            neuron.report_status("Processing stream".to_string());
+            loop {
+                let result = match inputs.message().await {
+                    Ok(Some(input)) => match neuron.admit() {
+                        Ok(_admitted) => neuron.forward(input.values, input.request_id, input.timestamp_ms).await,
+                        Err(status) => Err(status),
+                    },
+                    Ok(None) => break,
+                    Err(status) => Err(status),
+                };
+                let failed = result.is_err();
+                if sender.send(result).await.is_err() || failed {
+                    break;
+                }
+            }
+            // Leave the final status to `shutdown` when draining ended the stream.
+            if !neuron.drain.is_closed() {
+                neuron.report_status("Idle".to_string());
+            }
+        }));
+        ReceiverStream::new(receiver)
+    }
+
+    /// The neuron's current parameters, for a layer computing all of its
+    /// neurons in one pass.
+    pub(crate) async fn parameters(&self) -> RwLockReadGuard<'_, Parameters> {
+        self.state.parameters().await
+    }
+
+    /// Applies the activation function to the pre-activation `z` computed by
+    /// a layer pass.
+    pub(crate) fn activate(&self, z: f32) -> f32 {
+        self.activation.apply(self.modulation(ModulationTarget::ActivationGain) * z)
+    }
+
+    /// Records one output of a layer pass in the metrics window and stages
+    /// its persistence in the layer's `batch`.
+    pub(crate) fn record_layer_output(&self, batch: &mut NeuronBatch, latency: Duration, activation: f32) {
+        self.state.record_request();
+        self.metrics.record_output(latency, activation);
+        batch.put(&self.db, b"activation", &activation.to_ne_bytes());
+    }
+
+    /// Computes the neuron's output for one input vector, reporting its status
+    /// to the supervisor around it.
+    pub(crate) async fn fire(
+        &self,
+        values: Vec<f32>,
+        request_id: String,
+        timestamp_ms: u64,
+    ) -> Result<OutputSignal, Status> {
+        let _admitted = self.admit()?;
+        self.report_status("Processing input".to_string());
+        let output = self.forward(values, request_id, timestamp_ms).await;
+        self.report_status("Idle".to_string());
+        output
+    }
+
+    /// Computes the neuron's output for one input vector and records it in
+    /// the metrics window. Does not report status, so batched and streamed
+    /// inputs can report once.
+    async fn forward(
+        &self,
+        values: Vec<f32>,
+        request_id: String,
+        timestamp_ms: u64,
+    ) -> Result<OutputSignal, Status> {
+        let start_time = Instant::now();
+        self.state.record_request();
+        let result = async {
+            let extension_tokens = self.extension_tokens().await?;
+            let values = self.input_schema.assemble(values, extension_tokens)?;
+            self.compute(values, request_id, timestamp_ms).await
+        }
+        .await;
+        self.record_outcome(start_time, &result);
+        result
+    }
+
+    fn record_outcome(&self, start_time: Instant, result: &Result<OutputSignal, Status>) {
+        match result {
+            Ok(output) => self.metrics.record_output(start_time.elapsed(), output.value),
+            Err(_) => self.state.record_error(),
+        }
+    }
+
+    /// Takes the extension tokens that arrived since the last input, recording
+    /// the requests they came from on the current span.
+    async fn extension_tokens(&self) -> Result<Vec<f32>, Status> {
+        let mut extension_tokens = Vec::new();
+        let mut extension_request_ids = Vec::new();
+        for ExtensionTokens { request_id: extension_request_id, tokens } in self.process_extensions().await? {
+            log::debug!(
+                "Neuron {} consumed {} extension tokens of request {}",
+                self.id,
+                tokens.len(),
+                extension_request_id
+            );
+            extension_tokens.extend(tokens);
+            extension_request_ids.push(extension_request_id);
+        }
+        if !extension_request_ids.is_empty() {
+            Span::current().record("extension_request_ids", &extension_request_ids.join(",").as_str());
+        }
+        self.metrics.record_extension_tokens(extension_tokens.len());
+        Ok(extension_tokens)
+    }
+
+    /// Computes the neuron's output for one input vector assembled by the
+    /// input schema, applies any local plasticity and forwards the result to
+    /// downstream neurons.
+    async fn compute(
+        &self,
+        values: Vec<f32>,
+        request_id: String,
+        timestamp_ms: u64,
+    ) -> Result<OutputSignal, Status> {
+
+        let (z, weight_version, forward_weights) = {
+            let parameters = self.state.parameters().await;
+            let mut z = parameters.bias;
+            for (value, weight) in values.iter().zip(&parameters.weights) {
+                z += value * weight;
+            }
+            // Only passes that can be backpropagated keep a copy of the weights.
+            let forward_weights = if request_id.is_empty() {
+                None
+            } else {
+                Some(parameters.weights.clone())
+            };
+            (z, parameters.version, forward_weights)
+        };
+        let now_ms = if timestamp_ms == 0 { spiking::now_ms() } else { timestamp_ms };
+        let gain = self.modulation(ModulationTarget::ActivationGain);
+        let learning_rate_scale = self.modulation(ModulationTarget::LearningRate);
+        let (activation, membrane_potential, spiked) = match &self.spiking {
+            Some(spiking) => {
+                let threshold_scale = self.modulation(ModulationTarget::FiringThreshold);
+                let (membrane, spiked) = spiking.integrate(gain * z, now_ms, weight_version, threshold_scale, |membrane| {
+                    self.persist_membrane(membrane)
+                })?;
+                (if spiked { 1.0 } else { 0.0 }, membrane.potential, spiked)
+            }
+            None => (self.activation.apply(gain * z), 0.0, false),
+        };
+
+        if let Some(weights) = forward_weights {
+            let pass = ForwardPass {
+                inputs: values.clone(),
+                weights,
+                z,
+                gain,
+            };
+            self.state.cache_forward_pass(request_id.clone(), pass).await;
+        }
+
+        if let Some(learning) = self.learning.as_ref().filter(|l| !l.is_frozen()) {
+            let mut parameters = self.state.parameters_mut().await;
+            let mut deltas = learning.deltas(&values, activation, &parameters.weights);
+            let learning_state = serde_json::to_vec(&learning.state()).map_err(|e| {
+                log::error!("Failed to serialize learning state: {}", e);
+                Status::internal("Internal server error")
+            })?;
+            deltas.iter_mut().for_each(|delta| *delta *= learning_rate_scale);
+            parameters.apply_deltas(&deltas, 0.0);
+            let mut batch = NeuronBatch::new();
+            batch.put(&self.db, b"learning_state", &learning_state);
+            self.write_parameters(&parameters, batch)?;
+        }
+
+        if let Some(stdp) = &self.stdp {
+            let mut parameters = self.state.parameters_mut().await;
+            let (deltas, traces) = stdp.update(&values, spiked, now_ms, &parameters.weights, learning_rate_scale);
+            parameters.apply_deltas(&deltas, 0.0);
+            self.persist_parameters(&parameters)?;
+            self.persist_stdp_traces(&traces)?;
+        }
+
+        self.db
+            .put(b"activation", &activation.to_ne_bytes())
+            .map_err(|e| {
+                log::error!("Failed to store activation: {}", e);
+                Status::internal("Internal server error")
+            })?;
+
+        let output = OutputSignal {
+            value: activation,
+            weight_version,
+            membrane_potential,
+        };
+
+        if let Some(synapses) = &self.synapses {
+            // A spiking neuron that stays silent still sends its 0.0, or the
+            // waves it feeds downstream would never complete. Without a
+            // request id, fall back to the id of the call, which a client
+            // feeding several input neurons sends with each of them so that
+            // their activations meet in one wave downstream.
+            let wave_id = if request_id.is_empty() {
+                telemetry::current_request_id().unwrap_or_else(telemetry::new_request_id)
+            } else {
+                request_id
+            };
+            synapses.propagate(&self.id, &wave_id, activation, now_ms);
         }
-        Ok(input_tokens)
+
+        Ok(output)
+    }
+
+    fn modulation(&self, target: ModulationTarget) -> f32 {
+        self.neuromodulation
+            .as_ref()
+            .map(|neuromodulation| neuromodulation.factor(target))
+            .unwrap_or(1.0)
     }
 
-    async fn process_messenger_in(&mut self, message_hash_id: String, text: String) -> Result<(), Status> {
+    /// Persists weights, bias and version in one atomic write, so that a
+    /// crash never leaves a version next to weights it does not describe.
+    fn persist_parameters(&self, parameters: &Parameters) -> Result<(), Status> {
+        self.write_parameters(parameters, NeuronBatch::new())
+    }
+
+    /// Writes `parameters` together with whatever learning state `batch`
+    /// already holds, so that neither is stored without the other.
+    fn write_parameters(&self, parameters: &Parameters, mut batch: NeuronBatch) -> Result<(), Status> {
+        batch.put_f32s(&self.db, b"weights", &parameters.weights);
+        batch.put(&self.db, b"bias", &parameters.bias.to_ne_bytes());
+        batch.put(&self.db, b"weights_version", &parameters.version.to_ne_bytes());
+        self.db.write(batch).map_err(|e| {
+            log::error!("Failed to store parameters: {}", e);
+            Status::internal("Internal server error")
+        })?;
+
+        Ok(())
+    }
+
+    fn persist_membrane(&self, membrane: &MembraneState) -> Result<(), Status> {
+        let bytes = serde_json::to_vec(membrane).map_err(|e| {
+            log::error!("Failed to serialize membrane state: {}", e);
+            Status::internal("Internal server error")
+        })?;
+        self.db.put(b"membrane_state", &bytes).map_err(|e| {
+            log::error!("Failed to store membrane state: {}", e);
+            Status::internal("Internal server error")
+        })?;
+
+        Ok(())
+    }
+
+    fn persist_stdp_traces(&self, traces: &StdpTraces) -> Result<(), Status> {
+        let bytes = serde_json::to_vec(traces).map_err(|e| {
+            log::error!("Failed to serialize STDP traces: {}", e);
+            Status::internal("Internal server error")
+        })?;
+        self.db.put(b"stdp_traces", &bytes).map_err(|e| {
+            log::error!("Failed to store STDP traces: {}", e);
+            Status::internal("Internal server error")
+        })?;
+
+        Ok(())
+    }
+
+    async fn process_extensions(&self) -> Result<Vec<ExtensionTokens>, Status> {
+        Ok(self.state.drain_extension_tokens().await)
+    }
+
+    async fn process_messenger_in(&self, message_hash_id: String, text: String) -> Result<(), Status> {
         if let Some(ext) = &self.messenger_in_ext {
             ext.process_message(message_hash_id, text)
                 .await
@@ -119,7 +756,7 @@ impl Neuron {
         Ok(())
     }
 
-    async fn process_messenger_out(&mut self, message_hash_id: String, text: String) -> Result<(), Status> {
+    async fn process_messenger_out(&self, message_hash_id: String, text: String) -> Result<(), Status> {
         if let Some(ext) = &self.messenger_out_ext {
             ext.send_message(message_hash_id, text).await.map_err(|e| {
                 log::error!("MessengerOutExt error: {}", e);
@@ -130,62 +767,307 @@ impl Neuron {
     }
 }
 
-#[tonic::async_trait]
-impl NeuronService for Neuron {
-    async fn process_input(
+/// The RPCs `SharedNeuron` serves. Input streams need the neuron behind an
+/// `Arc`, so only `SharedNeuron` implements `NeuronService`.
+impl Neuron {
+    pub async fn process_input(
         &self,
         request: Request<InputSignal>,
     ) -> Result<Response<OutputSignal>, Status> {
-        let start_time = Instant::now();
-        let mut input = request.into_inner();
-        self.report_status("Processing input".to_string()).await;
-
-        let extension_tokens = self.process_extensions().await?;
-        input.values.extend(extension_tokens);
+        let context = telemetry::server_context("process_input", &request);
+        let InputSignal {
+            values,
+            request_id,
+            timestamp_ms,
+        } = request.into_inner();
+        let output = context.run(self.fire(values, request_id, timestamp_ms)).await?;
+        Ok(Response::new(output))
+    }
 
-        let mut z = 0.0;
-        for (i, &value) in input.values.iter().enumerate() {
-            z += value * self.weights[i];
+    pub async fn process_batch(
+        &self,
+        request: Request<InputBatch>,
+    ) -> Result<Response<OutputBatch>, Status> {
+        let context = telemetry::server_context("process_batch", &request);
+        let _admitted = self.admit()?;
+        let InputBatch { inputs } = request.into_inner();
+        // Assemble the whole batch before any input updates the neuron. The
+        // extension tokens that arrived since the last input go to the first.
+        let mut extension_tokens = self.extension_tokens().await?;
+        let mut batch = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let values = match self.input_schema.assemble(input.values, std::mem::take(&mut extension_tokens)) {
+                Ok(values) => values,
+                Err(e) => {
+                    self.state.record_error();
+                    return Err(e.into());
+                }
+            };
+            batch.push((values, input.request_id, input.timestamp_ms));
         }
-        let activation = self.activation.apply(z);
 
-        self.db
-            .put(b"activation", &activation.to_ne_bytes())
-            .map_err(|e| {
-                log::error!("Failed to store activation: {}", e);
-                Status::internal("Internal server error")
-            })?;
+        self.report_status("Processing batch".to_string());
+        let mut outputs = Vec::with_capacity(batch.len());
+        let result = context
+            .run(async {
+                for (values, request_id, timestamp_ms) in batch {
+                    let start_time = Instant::now();
+                    self.state.record_request();
+                    let result = self.compute(values, request_id, timestamp_ms).await;
+                    self.record_outcome(start_time, &result);
+                    outputs.push(result?);
+                }
+                Ok::<_, Status>(())
+            })
+            .await;
+        self.report_status("Idle".to_string());
+        result?;
+        Ok(Response::new(OutputBatch { outputs }))
+    }
 
-        let output = OutputSignal {
-            value: activation,
+    pub async fn receive_synaptic_input(
+        &self,
+        request: Request<SynapticInput>,
+    ) -> Result<Response<SynapticAck>, Status> {
+        let context = telemetry::server_context("receive_synaptic_input", &request);
+        let _admitted = self.admit()?;
+        let SynapticInput {
+            source_id,
+            slot,
+            value,
+            wave_id,
+            timestamp_ms,
+        } = request.into_inner();
+        let synapses = self
+            .synapses
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Neuron has no upstream synapses"))?;
+        let fired = match synapses.receive(&wave_id, &source_id, slot as usize, value)? {
+            Some(values) => {
+                context.run(self.fire(values, wave_id, timestamp_ms)).await?;
+                true
+            }
+            None => false,
         };
+        Ok(Response::new(SynapticAck { fired }))
+    }
 
-        let end_time = Instant::now();
-        let processing_time = end_time.duration_since(start_time).as_secs_f64();
-        let metrics = self.calculate_metrics(processing_time);
-        self.report_metrics(metrics).await;
-        self.report_status("Idle".to_string()).await;
+    pub async fn update_weights(
+        &self,
+        request: Request<WeightUpdate>,
+    ) -> Result<Response<()>, Status> {
+        let _admitted = self.admit()?;
+        let WeightUpdate { deltas, bias_delta } = request.into_inner();
+        self.input_schema.check_deltas(&deltas)?;
 
-        Ok(Response::new(output))
+        // Hold the write lock until the new parameters are persisted so that
+        // concurrent updates reach the database in version order.
+        let mut parameters = self.state.parameters_mut().await;
+        parameters.apply_deltas(&deltas, bias_delta);
+
+        self.persist_parameters(&parameters)?;
+
+        Ok(Response::new(()))
+    }
+
+    pub async fn get_parameters(
+        &self,
+        _request: Request<ParametersRequest>,
+    ) -> Result<Response<NeuronParameters>, Status> {
+        let parameters = self.state.parameters().await;
+        Ok(Response::new(NeuronParameters {
+            neuron_id: self.id.clone(),
+            weights: parameters.weights.clone(),
+            bias: parameters.bias,
+            version: parameters.version,
+        }))
+    }
+
+    pub async fn set_learning(
+        &self,
+        request: Request<LearningToggle>,
+    ) -> Result<Response<LearningState>, Status> {
+        let _admitted = self.admit()?;
+        let LearningToggle { frozen } = request.into_inner();
+        let learning = self
+            .learning
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("No learning rule configured"))?;
+        learning.set_frozen(frozen);
+        log::info!(
+            "Learning rule {} {}",
+            learning.rule_name(),
+            if frozen { "frozen" } else { "unfrozen" }
+        );
+        Ok(Response::new(LearningState {
+            rule: learning.rule_name().to_string(),
+            learning_rate: learning.learning_rate(),
+            frozen: learning.is_frozen(),
+        }))
+    }
+
+    pub async fn backward(
+        &self,
+        request: Request<BackwardRequest>,
+    ) -> Result<Response<BackwardResponse>, Status> {
+        let _admitted = self.admit()?;
+        let BackwardRequest {
+            request_id,
+            upstream_gradient,
+        } = request.into_inner();
+        let ForwardPass {
+            inputs,
+            weights,
+            z,
+            gain,
+        } = self
+            .state
+            .take_forward_pass(&request_id)
+            .await
+            .ok_or_else(|| Status::not_found(format!("No forward pass cached for request {}", request_id)))?;
+
+        let dz = upstream_gradient * self.activation.derivative(gain * z) * gain;
+
+        let input_gradients: Vec<f32> = weights.iter().map(|w| dz * w).collect();
+
+        let mut parameters = self.state.parameters_mut().await;
+
+        // Flattened as [weights..., bias] for the optimizer.
+        let mut gradients: Vec<f32> = inputs.iter().map(|x| dz * x).collect();
+        gradients.push(dz);
+        let (mut deltas, optimizer_state) = {
+            let mut optimizer = self.optimizer.lock().expect("Optimizer lock poisoned");
+            let deltas = optimizer.deltas(&gradients);
+            (deltas, optimizer.state())
+        };
+        let optimizer_state = serde_json::to_vec(&optimizer_state).map_err(|e| {
+            log::error!("Failed to serialize optimizer state: {}", e);
+            Status::internal("Internal server error")
+        })?;
+        let learning_rate_scale = self.modulation(ModulationTarget::LearningRate);
+        deltas.iter_mut().for_each(|delta| *delta *= learning_rate_scale);
+        let bias_delta = deltas.pop().unwrap_or(0.0);
+        parameters.apply_deltas(&deltas, bias_delta);
+        let mut batch = NeuronBatch::new();
+        batch.put(&self.db, b"optimizer_state", &optimizer_state);
+        self.write_parameters(&parameters, batch)?;
+
+        Ok(Response::new(BackwardResponse {
+            input_gradients,
+            weight_version: parameters.version,
+        }))
+    }
+
+    pub async fn subscribe_spikes(
+        &self,
+        _request: Request<SpikeSubscription>,
+    ) -> Result<Response<ReceiverStream<Result<SpikeEvent, Status>>>, Status> {
+        let mut spikes = self
+            .spiking
+            .as_ref()
+            .ok_or_else(|| Status::failed_precondition("Neuron is not in spiking mode"))?
+            .subscribe();
+        let neuron_id = self.id.clone();
+        let (sender, receiver) = mpsc::channel(32);
+        tokio::spawn(async move {
+            loop {
+                let event = match spikes.recv().await {
+                    Ok(spike) => SpikeEvent {
+                        neuron_id: neuron_id.clone(),
+                        timestamp_ms: spike.timestamp_ms,
+                        weight_version: spike.weight_version,
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        log::warn!("Spike subscriber lagged, skipped {} spikes", skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if sender.send(Ok(event)).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Serves a neuron shared with other tasks. Delegates to `Neuron`, except that
+/// it hands the neuron to the task serving an input stream.
+#[derive(Clone)]
+pub struct SharedNeuron(Arc<Neuron>);
+
+impl SharedNeuron {
+    pub fn new(neuron: Arc<Neuron>) -> Self {
+        Self(neuron)
+    }
+}
+
+#[tonic::async_trait]
+impl NeuronService for SharedNeuron {
+    type SubscribeSpikesStream = ReceiverStream<Result<SpikeEvent, Status>>;
+    type ProcessInputStreamStream = ReceiverStream<Result<OutputSignal, Status>>;
+
+    async fn process_input(
+        &self,
+        request: Request<InputSignal>,
+    ) -> Result<Response<OutputSignal>, Status> {
+        self.0.process_input(request).await
+    }
+
+    async fn process_batch(
+        &self,
+        request: Request<InputBatch>,
+    ) -> Result<Response<OutputBatch>, Status> {
+        self.0.process_batch(request).await
+    }
+
+    async fn process_input_stream(
+        &self,
+        request: Request<Streaming<InputSignal>>,
+    ) -> Result<Response<Self::ProcessInputStreamStream>, Status> {
+        Ok(Response::new(Neuron::spawn_input_stream(self.0.clone(), request)))
+    }
+
+    async fn receive_synaptic_input(
+        &self,
+        request: Request<SynapticInput>,
+    ) -> Result<Response<SynapticAck>, Status> {
+        self.0.receive_synaptic_input(request).await
     }
 
     async fn update_weights(
         &self,
         request: Request<WeightUpdate>,
     ) -> Result<Response<()>, Status> {
-        let WeightUpdate { deltas } = request.into_inner();
+        self.0.update_weights(request).await
+    }
 
-        for (i, &delta) in deltas.iter().enumerate() {
-            self.weights[i] += delta;
-        }
+    async fn get_parameters(
+        &self,
+        request: Request<ParametersRequest>,
+    ) -> Result<Response<NeuronParameters>, Status> {
+        self.0.get_parameters(request).await
+    }
 
-        self.db
-            .put(b"weights", &self.weights.iter().map(|&w| w.to_ne_bytes()).flatten().collect::<Vec<_>>())
-            .map_err(|e| {
-                log::error!("Failed to store weights: {}", e);
-                Status::internal("Internal server error")
-            })?;
+    async fn set_learning(
+        &self,
+        request: Request<LearningToggle>,
+    ) -> Result<Response<LearningState>, Status> {
+        self.0.set_learning(request).await
+    }
 
-        Ok(Response::new(()))
+    async fn backward(
+        &self,
+        request: Request<BackwardRequest>,
+    ) -> Result<Response<BackwardResponse>, Status> {
+        self.0.backward(request).await
     }
-}
\ No newline at end of file
+
+    async fn subscribe_spikes(
+        &self,
+        request: Request<SpikeSubscription>,
+    ) -> Result<Response<Self::SubscribeSpikesStream>, Status> {
+        self.0.subscribe_spikes(request).await
+    }
+}
@@ -3,6 +3,17 @@ use std::fmt::Debug;
 
 pub trait Activation: Send + Sync + Debug {
     fn apply(&self, x: f32) -> f32;
+    /// Derivative of `apply` with respect to the pre-activation `x`.
+    fn derivative(&self, x: f32) -> f32;
+    fn name(&self) -> &'static str;
+}
+
+pub fn from_name(name: &str) -> Option<Box<dyn Activation>> {
+    match name {
+        "relu" => Some(Box::new(ReLU)),
+        "sigmoid" => Some(Box::new(Sigmoid)),
+        _ => None,
+    }
 }
 
 #[derive(Debug)]
@@ -12,6 +23,18 @@ impl Activation for ReLU {
     fn apply(&self, x: f32) -> f32 {
         x.max(0.0)
     }
+
+    fn derivative(&self, x: f32) -> f32 {
+        if x > 0.0 {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        "relu"
+    }
 }
 
 #[derive(Debug)]
@@ -21,4 +44,13 @@ impl Activation for Sigmoid {
     fn apply(&self, x: f32) -> f32 {
         1.0 / (1.0 + (-x).exp())
     }
+
+    fn derivative(&self, x: f32) -> f32 {
+        let s = self.apply(x);
+        s * (1.0 - s)
+    }
+
+    fn name(&self) -> &'static str {
+        "sigmoid"
+    }
 }
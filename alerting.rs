@@ -0,0 +1,261 @@
+// alerting.rs
+use crate::metrics_exporter::NeuronSnapshot;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AlertError {
+    #[error("Failed to read alert rules: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse alert rules: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Alert rule {0} is declared more than once")]
+    DuplicateRule(String),
+    #[error("Silence of {0} for {1:?} is too long")]
+    SilenceTooLong(String, Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    Below,
+}
+
+/// What a rule checks for each neuron.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// The neuron's latest `metric` is above or below `threshold`.
+    Metric {
+        metric: String,
+        comparison: Comparison,
+        threshold: f64,
+    },
+    /// The neuron's status is `status`, e.g. `Unreachable`.
+    Status { status: String },
+}
+
+impl Condition {
+    fn holds(&self, neuron: &NeuronSnapshot) -> Option<String> {
+        match self {
+            Condition::Metric {
+                metric,
+                comparison,
+                threshold,
+            } => {
+                let value = *neuron.metrics.get(metric)?;
+                let holds = match comparison {
+                    Comparison::Above => value > *threshold,
+                    Comparison::Below => value < *threshold,
+                };
+                if holds {
+                    Some(format!("{} is {} ({:?} {})", metric, value, comparison, threshold))
+                } else {
+                    None
+                }
+            }
+            Condition::Status { status } => {
+                if neuron.status.as_deref() == Some(status.as_str()) {
+                    Some(format!("status is {}", status))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// An alert that fires for a neuron once `condition` has held for `for_secs`.
+///
+/// ```toml
+/// [[rules]]
+/// name = "slow"
+/// kind = "metric"
+/// metric = "latency_p99"
+/// comparison = "above"
+/// threshold = 0.5
+/// for_secs = 300
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+    #[serde(default)]
+    pub for_secs: u64,
+    /// Neurons the rule applies to; all of them if empty.
+    #[serde(default)]
+    pub neurons: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AlertRules {
+    #[serde(default)]
+    rules: Vec<AlertRule>,
+}
+
+pub fn load_rules<P: AsRef<Path>>(path: P) -> Result<Vec<AlertRule>, AlertError> {
+    parse_rules(&std::fs::read_to_string(path)?)
+}
+
+pub fn parse_rules(contents: &str) -> Result<Vec<AlertRule>, AlertError> {
+    let AlertRules { rules } = toml::from_str(contents)?;
+    let mut names = HashSet::new();
+    for rule in &rules {
+        if !names.insert(rule.name.as_str()) {
+            return Err(AlertError::DuplicateRule(rule.name.clone()));
+        }
+    }
+    Ok(rules)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// A change in an alert's state, to be pushed to the chat.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub rule: String,
+    pub neuron_id: String,
+    pub state: AlertState,
+    pub message: String,
+}
+
+/// Evaluates alert rules against neuron snapshots. Each (rule, neuron) pair
+/// notifies once when it starts firing and once when it resolves; silenced
+/// pairs change state without notifying.
+pub struct AlertEngine {
+    rules: Vec<AlertRule>,
+    // When each (rule, neuron) condition started holding.
+    pending: HashMap<(String, String), Instant>,
+    firing: HashMap<(String, String), String>,
+    // Rule names or neuron ids, silenced until the given time.
+    silences: HashMap<String, Instant>,
+}
+
+impl AlertEngine {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            pending: HashMap::new(),
+            firing: HashMap::new(),
+            silences: HashMap::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    pub fn evaluate(&mut self, neurons: &[NeuronSnapshot], now: Instant) -> Vec<Notification> {
+        self.silences.retain(|_, until| *until > now);
+        let mut notifications = Vec::new();
+        // Forget neurons that left the snapshot, so a neuron that comes back
+        // under the same id starts over, and resolve what was firing for them.
+        let present: HashSet<&str> = neurons.iter().map(|neuron| neuron.neuron_id.as_str()).collect();
+        self.pending.retain(|(_, neuron_id), _| present.contains(neuron_id.as_str()));
+        let mut gone: Vec<(String, String)> = self
+            .firing
+            .keys()
+            .filter(|(_, neuron_id)| !present.contains(neuron_id.as_str()))
+            .cloned()
+            .collect();
+        gone.sort();
+        for key in gone {
+            self.firing.remove(&key);
+            let (rule, neuron_id) = key;
+            if !self.silences.contains_key(&rule) && !self.silences.contains_key(&neuron_id) {
+                notifications.push(Notification {
+                    message: format!("[RESOLVED] {}: Neuron {} is gone", rule, neuron_id),
+                    rule,
+                    neuron_id,
+                    state: AlertState::Resolved,
+                });
+            }
+        }
+        for rule in &self.rules {
+            for neuron in neurons {
+                if !rule.neurons.is_empty() && !rule.neurons.contains(&neuron.neuron_id) {
+                    continue;
+                }
+                let key = (rule.name.clone(), neuron.neuron_id.clone());
+                let silenced = self.silences.contains_key(&rule.name) || self.silences.contains_key(&neuron.neuron_id);
+                match rule.condition.holds(neuron) {
+                    Some(reason) => {
+                        let since = *self.pending.entry(key.clone()).or_insert(now);
+                        let held_long_enough =
+                            now.saturating_duration_since(since) >= Duration::from_secs(rule.for_secs);
+                        if held_long_enough && !self.firing.contains_key(&key) {
+                            self.firing.insert(key, reason.clone());
+                            if !silenced {
+                                notifications.push(Notification {
+                                    rule: rule.name.clone(),
+                                    neuron_id: neuron.neuron_id.clone(),
+                                    state: AlertState::Firing,
+                                    message: format!("[FIRING] {}: Neuron {} {}", rule.name, neuron.neuron_id, reason),
+                                });
+                            }
+                        }
+                    }
+                    None => {
+                        self.pending.remove(&key);
+                        if self.firing.remove(&key).is_some() && !silenced {
+                            notifications.push(Notification {
+                                rule: rule.name.clone(),
+                                neuron_id: neuron.neuron_id.clone(),
+                                state: AlertState::Resolved,
+                                message: format!("[RESOLVED] {}: Neuron {}", rule.name, neuron.neuron_id),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        notifications
+    }
+
+    /// Suppresses notifications for a rule name or neuron id for `duration`;
+    /// a zero duration lifts the silence. Fails if `duration` runs past what
+    /// an `Instant` can represent.
+    pub fn silence(&mut self, target: String, duration: Duration, now: Instant) -> Result<(), AlertError> {
+        if duration == Duration::from_secs(0) {
+            self.silences.remove(&target);
+        } else {
+            let until = now
+                .checked_add(duration)
+                .ok_or_else(|| AlertError::SilenceTooLong(target.clone(), duration))?;
+            self.silences.insert(target, until);
+        }
+        Ok(())
+    }
+
+    /// Active silences and the time left on each, sorted by target.
+    pub fn silences(&self, now: Instant) -> Vec<(String, Duration)> {
+        let mut silences: Vec<(String, Duration)> = self
+            .silences
+            .iter()
+            .filter(|(_, until)| **until > now)
+            .map(|(target, until)| (target.clone(), until.saturating_duration_since(now)))
+            .collect();
+        silences.sort();
+        silences
+    }
+
+    /// Currently firing alerts as (rule, neuron id, reason), sorted.
+    pub fn firing(&self) -> Vec<(String, String, String)> {
+        let mut firing: Vec<(String, String, String)> = self
+            .firing
+            .iter()
+            .map(|((rule, neuron_id), reason)| (rule.clone(), neuron_id.clone(), reason.clone()))
+            .collect();
+        firing.sort();
+        firing
+    }
+}
@@ -1,7 +1,19 @@
+use std::env;
+use std::path::PathBuf;
+
 fn main() {
-    tonic_build::compile_protos("proto/neuron.proto").unwrap();
-    tonic_build::compile_protos("proto/supervisor.proto").unwrap();
-    tonic_build::compile_protos("proto/eye_ext.proto").unwrap();
-    tonic_build::compile_protos("proto/webhook_ext.proto").unwrap();
-    tonic_build::compile_protos("proto/messenger_ext.proto").unwrap();
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("neurox_descriptor.bin"))
+        .compile(
+            &[
+                "proto/neuron.proto",
+                "proto/supervisor.proto",
+                "proto/eye_ext.proto",
+                "proto/webhook_ext.proto",
+                "proto/messenger_ext.proto",
+            ],
+            &["proto"],
+        )
+        .unwrap();
 }
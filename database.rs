@@ -1,16 +1,23 @@
 // database.rs
-use rocksdb::{DB, Options};
+use rocksdb::{Options, WriteBatch, DB};
 use std::path::Path;
+use std::sync::Arc;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum DatabaseError {
     #[error("RocksDB error: {0}")]
     RocksDb(#[from] rocksdb::Error),
+    #[error("Corrupt value for key {0}")]
+    Corrupt(String),
 }
 
+/// A RocksDB handle, optionally scoped to one neuron's key prefix so that many
+/// neurons can share a single database.
+#[derive(Clone)]
 pub struct NeuronDb {
-    db: DB,
+    db: Arc<DB>,
+    prefix: Vec<u8>,
 }
 
 impl NeuronDb {
@@ -18,11 +25,31 @@ impl NeuronDb {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         let db = DB::open(&opts, path)?;
-        Ok(Self { db })
+        Ok(Self {
+            db: Arc::new(db),
+            prefix: Vec::new(),
+        })
+    }
+
+    /// Returns a handle to the same database whose keys live under `neuron_id/`.
+    pub fn scoped(&self, neuron_id: &str) -> Self {
+        let mut prefix = self.prefix.clone();
+        prefix.extend_from_slice(neuron_id.as_bytes());
+        prefix.push(b'/');
+        Self {
+            db: self.db.clone(),
+            prefix,
+        }
+    }
+
+    fn key(&self, key: &[u8]) -> Vec<u8> {
+        let mut scoped = self.prefix.clone();
+        scoped.extend_from_slice(key);
+        scoped
     }
 
     pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
-        match self.db.get(key) {
+        match self.db.get(self.key(key)) {
             Ok(Some(value)) => Ok(Some(value.to_vec())),
             Ok(None) => Ok(None),
             Err(e) => Err(DatabaseError::from(e)),
@@ -30,12 +57,83 @@ impl NeuronDb {
     }
 
     pub fn put(&self, key: &[u8], value: &[u8]) -> Result<(), DatabaseError> {
-        self.db.put(key, value)?;
+        self.db.put(self.key(key), value)?;
+        Ok(())
+    }
+
+    /// Applies every write in `batch` atomically. The batch may hold writes
+    /// for any scope of this database.
+    pub fn write(&self, batch: NeuronBatch) -> Result<(), DatabaseError> {
+        self.db.write(batch.batch)?;
         Ok(())
     }
 
+    /// Writes buffered writes of the whole database, not just this scope, to disk.
+    pub fn flush(&self) -> Result<(), DatabaseError> {
+        self.db.flush()?;
+        Ok(())
+    }
+
+    pub fn get_f32s(&self, key: &[u8]) -> Result<Option<Vec<f32>>, DatabaseError> {
+        let value = match self.get(key)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        if value.len() % 4 != 0 {
+            return Err(DatabaseError::Corrupt(String::from_utf8_lossy(key).into_owned()));
+        }
+        Ok(Some(
+            value
+                .chunks(4)
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        ))
+    }
+
+    pub fn put_f32s(&self, key: &[u8], values: &[f32]) -> Result<(), DatabaseError> {
+        self.put(key, &f32s_to_bytes(values))
+    }
+
+    pub fn get_u64(&self, key: &[u8]) -> Result<Option<u64>, DatabaseError> {
+        match self.get(key)? {
+            Some(value) if value.len() == 8 => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&value);
+                Ok(Some(u64::from_ne_bytes(bytes)))
+            }
+            Some(_) => Err(DatabaseError::Corrupt(String::from_utf8_lossy(key).into_owned())),
+            None => Ok(None),
+        }
+    }
+
     pub fn delete(&self, key: &[u8]) -> Result<(), DatabaseError> {
-        self.db.delete(key)?;
+        self.db.delete(self.key(key))?;
         Ok(())
     }
 }
+
+/// Writes collected from one or more scopes of a database and applied
+/// together by `NeuronDb::write`.
+#[derive(Default)]
+pub struct NeuronBatch {
+    batch: WriteBatch,
+}
+
+impl NeuronBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stages a write of `key` under `db`'s scope.
+    pub fn put(&mut self, db: &NeuronDb, key: &[u8], value: &[u8]) {
+        self.batch.put(db.key(key), value);
+    }
+
+    pub fn put_f32s(&mut self, db: &NeuronDb, key: &[u8], values: &[f32]) {
+        self.put(db, key, &f32s_to_bytes(values));
+    }
+}
+
+fn f32s_to_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
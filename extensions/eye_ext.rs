@@ -1,6 +1,8 @@
 // extensions/eye_ext.rs
 use crate::proto::eye_ext_server::{EyeExt as EyeExtTrait, EyeExtServer};
 use crate::proto::{EyeExtRequest, EyeExtResponse};
+use crate::neuron_state::ExtensionTokens;
+use crate::telemetry;
 use std::convert::Infallible;
 use thiserror::Error;
 use tokio::sync::mpsc;
@@ -9,17 +11,17 @@ use tonic::{transport::Server, Request, Response, Status};
 #[derive(Error, Debug)]
 pub enum EyeExtError {
     #[error("Channel send error: {0}")]
-    ChannelSend(#[from] mpsc::error::SendError<Vec<f32>>),
+    ChannelSend(#[from] mpsc::error::SendError<ExtensionTokens>),
     #[error("Image processing error: {0}")]
     ImageProcessing(String),
 }
 
 pub struct EyeExt {
-    sender: mpsc::Sender<Vec<f32>>,
+    sender: mpsc::Sender<ExtensionTokens>,
 }
 
 impl EyeExt {
-    pub fn new(sender: mpsc::Sender<Vec<f32>>) -> Self {
+    pub fn new(sender: mpsc::Sender<ExtensionTokens>) -> Self {
         Self { sender }
     }
 }
@@ -30,10 +32,17 @@ impl EyeExtTrait for EyeExt {
         &self,
         request: Request<EyeExtRequest>,
     ) -> Result<Response<EyeExtResponse>, Status> {
+        let context = telemetry::server_context("process_image", &request);
         let EyeExtRequest { image_data } = request.into_inner();
-        let tokens = self.preprocess_and_tokenize(image_data)?;
-        self.sender.send(tokens).await?;
-        Ok(Response::new(EyeExtResponse {}))
+        let request_id = context.request_id().to_string();
+        context
+            .run(async {
+                let tokens = self.preprocess_and_tokenize(image_data)?;
+                log::debug!("Tokenized image into {} tokens", tokens.len());
+                self.sender.send(ExtensionTokens { request_id, tokens }).await?;
+                Ok(Response::new(EyeExtResponse {}))
+            })
+            .await
     }
 }
 
@@ -1,6 +1,8 @@
 // extensions/webhook_ext.rs
 use crate::proto::webhook_ext_server::{WebhookExt as WebhookExtTrait, WebhookExtServer};
 use crate::proto::{WebhookExtRequest, WebhookExtResponse};
+use crate::neuron_state::ExtensionTokens;
+use crate::telemetry;
 use std::convert::Infallible;
 use thiserror::Error;
 use tokio::sync::mpsc;
@@ -9,17 +11,17 @@ use tonic::{transport::Server, Request, Response, Status};
 #[derive(Error, Debug)]
 pub enum WebhookExtError {
     #[error("Channel send error: {0}")]
-    ChannelSend(#[from] mpsc::error::SendError<Vec<f32>>),
+    ChannelSend(#[from] mpsc::error::SendError<ExtensionTokens>),
     #[error("JSON processing error: {0}")]
     JsonProcessing(String),
 }
 
 pub struct WebhookStreamExt {
-    sender: mpsc::Sender<Vec<f32>>,
+    sender: mpsc::Sender<ExtensionTokens>,
 }
 
 impl WebhookStreamExt {
-    pub fn new(sender: mpsc::Sender<Vec<f32>>) -> Self {
+    pub fn new(sender: mpsc::Sender<ExtensionTokens>) -> Self {
         Self { sender }
     }
 }
@@ -30,10 +32,17 @@ impl WebhookExtTrait for WebhookStreamExt {
         &self,
         request: Request<WebhookExtRequest>,
     ) -> Result<Response<WebhookExtResponse>, Status> {
+        let context = telemetry::server_context("process_json", &request);
         let WebhookExtRequest { json_data } = request.into_inner();
-        let tokens = self.parse_and_tokenize(json_data)?;
-        self.sender.send(tokens).await?;
-        Ok(Response::new(WebhookExtResponse {}))
+        let request_id = context.request_id().to_string();
+        context
+            .run(async {
+                let tokens = self.parse_and_tokenize(json_data)?;
+                log::debug!("Tokenized JSON into {} tokens", tokens.len());
+                self.sender.send(ExtensionTokens { request_id, tokens }).await?;
+                Ok(Response::new(WebhookExtResponse {}))
+            })
+            .await
     }
 }
 
@@ -0,0 +1,44 @@
+// health.rs
+use crate::proto;
+use std::time::Duration;
+use tonic::transport::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_reflection::server::{ServerReflection, ServerReflectionServer};
+
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Keeps the `grpc.health.v1` status of service `S` in step with `ready`,
+/// polling it every `CHECK_INTERVAL`. `S` reports `NOT_SERVING` until the
+/// first check passes. Never returns; run it alongside the server.
+pub async fn report_readiness<S, F>(mut reporter: HealthReporter, ready: F)
+where
+    S: NamedService,
+    F: Fn() -> bool,
+{
+    reporter.set_not_serving::<S>().await;
+    let mut serving = false;
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let is_ready = ready();
+        if is_ready == serving {
+            continue;
+        }
+        if is_ready {
+            log::info!("{} is serving", S::NAME);
+            reporter.set_serving::<S>().await;
+        } else {
+            log::warn!("{} is not serving", S::NAME);
+            reporter.set_not_serving::<S>().await;
+        }
+        serving = is_ready;
+    }
+}
+
+/// gRPC server reflection over every service defined in `proto/`.
+pub fn reflection_service() -> ServerReflectionServer<impl ServerReflection> {
+    tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(proto::FILE_DESCRIPTOR_SET)
+        .build()
+        .expect("Embedded file descriptor set is valid")
+}
@@ -0,0 +1,135 @@
+// input_schema.rs
+use thiserror::Error;
+use tonic::Status;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum InputSchemaError {
+    #[error("Input size mismatch: expected {expected} values, received {received}")]
+    InputSize { expected: usize, received: usize },
+    #[error("Extension size mismatch: expected {expected} tokens, received {received}")]
+    ExtensionSize { expected: usize, received: usize },
+    #[error("Delta size mismatch: expected {expected} deltas, received {received}")]
+    DeltaSize { expected: usize, received: usize },
+}
+
+impl From<InputSchemaError> for Status {
+    fn from(e: InputSchemaError) -> Self {
+        Status::invalid_argument(e.to_string())
+    }
+}
+
+/// How extension tokens that don't fill the extension region exactly are handled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtensionPolicy {
+    /// Reject any token count other than the declared extension size.
+    Strict,
+    /// Zero-pad short token runs, reject long ones.
+    Pad,
+    /// Truncate long token runs, reject short ones.
+    Truncate,
+    /// Zero-pad short token runs and truncate long ones.
+    PadAndTruncate,
+}
+
+/// Declared layout of the values a neuron accepts, one weight per value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSchema {
+    /// Exactly `size` values, extension tokens included.
+    Fixed { size: usize },
+    /// `core_size` request values followed by an `extension_size` region
+    /// filled from extension tokens according to `policy`.
+    Extended {
+        core_size: usize,
+        extension_size: usize,
+        policy: ExtensionPolicy,
+    },
+}
+
+impl InputSchema {
+    pub fn fixed(size: usize) -> Self {
+        InputSchema::Fixed { size }
+    }
+
+    pub fn extended(core_size: usize, extension_size: usize, policy: ExtensionPolicy) -> Self {
+        InputSchema::Extended {
+            core_size,
+            extension_size,
+            policy,
+        }
+    }
+
+    /// Total number of values, and therefore weights, the schema describes.
+    pub fn size(&self) -> usize {
+        match self {
+            InputSchema::Fixed { size } => *size,
+            InputSchema::Extended {
+                core_size,
+                extension_size,
+                ..
+            } => core_size + extension_size,
+        }
+    }
+
+    /// Number of values a request supplies itself, before extension tokens.
+    pub fn core_size(&self) -> usize {
+        match self {
+            InputSchema::Fixed { size } => *size,
+            InputSchema::Extended { core_size, .. } => *core_size,
+        }
+    }
+
+    /// Combines request values and extension tokens into a vector of exactly `size()` values.
+    pub fn assemble(
+        &self,
+        mut values: Vec<f32>,
+        mut extension_tokens: Vec<f32>,
+    ) -> Result<Vec<f32>, InputSchemaError> {
+        match self {
+            InputSchema::Fixed { size } => {
+                values.extend(extension_tokens);
+                if values.len() != *size {
+                    return Err(InputSchemaError::InputSize {
+                        expected: *size,
+                        received: values.len(),
+                    });
+                }
+                Ok(values)
+            }
+            InputSchema::Extended {
+                core_size,
+                extension_size,
+                policy,
+            } => {
+                if values.len() != *core_size {
+                    return Err(InputSchemaError::InputSize {
+                        expected: *core_size,
+                        received: values.len(),
+                    });
+                }
+                let received = extension_tokens.len();
+                let can_pad = matches!(policy, ExtensionPolicy::Pad | ExtensionPolicy::PadAndTruncate);
+                let can_truncate =
+                    matches!(policy, ExtensionPolicy::Truncate | ExtensionPolicy::PadAndTruncate);
+                if (received < *extension_size && !can_pad) || (received > *extension_size && !can_truncate) {
+                    return Err(InputSchemaError::ExtensionSize {
+                        expected: *extension_size,
+                        received,
+                    });
+                }
+                extension_tokens.resize(*extension_size, 0.0);
+                values.extend(extension_tokens);
+                Ok(values)
+            }
+        }
+    }
+
+    pub fn check_deltas(&self, deltas: &[f32]) -> Result<(), InputSchemaError> {
+        if deltas.len() != self.size() {
+            return Err(InputSchemaError::DeltaSize {
+                expected: self.size(),
+                received: deltas.len(),
+            });
+        }
+        Ok(())
+    }
+}
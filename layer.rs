@@ -0,0 +1,205 @@
+// layer.rs
+use crate::activation;
+use crate::database::{NeuronBatch, NeuronDb};
+use crate::input_schema::{InputSchema, InputSchemaError};
+use crate::neuron::{Neuron, NeuronError, ResizePolicy};
+use crate::proto::layer_service_server::LayerService;
+use crate::proto::{
+    InputSignal, LayerNeuronsRequest, LayerNeuronsResponse, LayerOutput, NeuronInput, NeuronParameters,
+    NeuronRef, OutputSignal, ParametersRequest,
+};
+use crate::reporter::Reporter;
+use crate::telemetry;
+use crate::weight_init::WeightInitializer;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tonic::{Request, Response, Status};
+
+/// Many neurons sharing one process, one `NeuronDb` and one gRPC server. Every
+/// neuron keeps its own id, so it still reports metrics to the supervisor
+/// individually; layer inputs are reported once, under the layer's id.
+pub struct Layer {
+    id: String,
+    input_schema: InputSchema,
+    neurons: Vec<Arc<Neuron>>,
+    index: HashMap<String, usize>,
+    db: NeuronDb,
+    reporter: Reporter,
+}
+
+impl Layer {
+    /// Opens (or restores) `size` neurons named `<id>_<i>` in the database at `id`.
+    pub fn open(
+        id: String,
+        size: usize,
+        input_schema: InputSchema,
+        activation_name: &str,
+        weight_initializer: &dyn WeightInitializer,
+        resize_policy: ResizePolicy,
+    ) -> Result<Self, NeuronError> {
+        let db = NeuronDb::new(&id)?;
+        let reporter = Reporter::from_env();
+        let mut neurons = Vec::with_capacity(size);
+        let mut index = HashMap::new();
+        for i in 0..size {
+            let neuron_id = format!("{}_{}", id, i);
+            let activation = activation::from_name(activation_name)
+                .ok_or_else(|| NeuronError::UnknownActivation(activation_name.to_string()))?;
+            let neuron = Neuron::open_with_db(
+                neuron_id.clone(),
+                db.scoped(&neuron_id),
+                input_schema.clone(),
+                activation,
+                weight_initializer,
+                resize_policy,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )?
+            .with_reporter(reporter.clone());
+            index.insert(neuron_id, i);
+            neurons.push(Arc::new(neuron));
+        }
+        log::info!("Opened layer {} with {} neurons", id, size);
+        Ok(Self {
+            id,
+            input_schema,
+            neurons,
+            index,
+            db,
+            reporter,
+        })
+    }
+
+    /// Reports every neuron's metrics to the supervisor every `interval`.
+    pub fn spawn_metrics_reporting(&self, interval: Duration) -> Vec<JoinHandle<()>> {
+        self.neurons
+            .iter()
+            .map(|neuron| Neuron::spawn_metrics_reporting(neuron, interval))
+            .collect()
+    }
+
+    pub fn neurons(&self) -> Vec<Arc<Neuron>> {
+        self.neurons.clone()
+    }
+
+    /// Computes every neuron's output for `values` as one matrix-vector
+    /// product, one row per neuron, and persists all outputs in one write.
+    async fn forward(&self, values: &[f32]) -> Result<LayerOutput, Status> {
+        let start_time = Instant::now();
+        let mut z = Vec::with_capacity(self.neurons.len());
+        let mut weight_versions = Vec::with_capacity(self.neurons.len());
+        {
+            // Hold every row until the product is done, so the output never
+            // mixes weights from before and after an update.
+            let mut rows = Vec::with_capacity(self.neurons.len());
+            for neuron in &self.neurons {
+                rows.push(neuron.parameters().await);
+            }
+            for row in &rows {
+                let mut sum = row.bias;
+                for (value, weight) in values.iter().zip(&row.weights) {
+                    sum += value * weight;
+                }
+                z.push(sum);
+                weight_versions.push(row.version);
+            }
+        }
+
+        let outputs: Vec<f32> = self
+            .neurons
+            .iter()
+            .zip(&z)
+            .map(|(neuron, &z)| neuron.activate(z))
+            .collect();
+        let latency = start_time.elapsed();
+        let mut batch = NeuronBatch::new();
+        for (neuron, &output) in self.neurons.iter().zip(&outputs) {
+            neuron.record_layer_output(&mut batch, latency, output);
+        }
+        self.db.write(batch).map_err(|e| {
+            log::error!("Failed to store activations of layer {}: {}", self.id, e);
+            Status::internal("Internal server error")
+        })?;
+
+        Ok(LayerOutput {
+            values: outputs,
+            weight_versions,
+        })
+    }
+
+    fn neuron(&self, neuron_id: &str) -> Result<&Arc<Neuron>, Status> {
+        self.index
+            .get(neuron_id)
+            .map(|&i| &self.neurons[i])
+            .ok_or_else(|| Status::not_found(format!("Neuron {} is not in layer {}", neuron_id, self.id)))
+    }
+}
+
+#[tonic::async_trait]
+impl LayerService for Layer {
+    async fn process_layer_input(
+        &self,
+        request: Request<InputSignal>,
+    ) -> Result<Response<LayerOutput>, Status> {
+        let context = telemetry::server_context("process_layer_input", &request);
+        // Layer neurons neither spike nor keep forward passes for `Backward`,
+        // so only the values matter.
+        let InputSignal { values, .. } = request.into_inner();
+        if values.len() != self.input_schema.core_size() {
+            return Err(InputSchemaError::InputSize {
+                expected: self.input_schema.core_size(),
+                received: values.len(),
+            }
+            .into());
+        }
+
+        let values = self.input_schema.assemble(values, Vec::new())?;
+        let _admitted = self
+            .neurons
+            .iter()
+            .map(|neuron| neuron.admit())
+            .collect::<Result<Vec<_>, _>>()?;
+        self.reporter.report_status(&self.id, "Processing input".to_string());
+        let output = context.run(self.forward(&values)).await;
+        self.reporter.report_status(&self.id, "Idle".to_string());
+        Ok(Response::new(output?))
+    }
+
+    async fn process_neuron_input(
+        &self,
+        request: Request<NeuronInput>,
+    ) -> Result<Response<OutputSignal>, Status> {
+        let context = telemetry::server_context("process_neuron_input", &request);
+        let NeuronInput { neuron_id, input } = request.into_inner();
+        let input = input.ok_or_else(|| Status::invalid_argument("Missing input"))?;
+        let neuron = self.neuron(&neuron_id)?;
+        context
+            .run(async { neuron.process_input(telemetry::traced_request(input)).await })
+            .await
+    }
+
+    async fn get_neuron_parameters(
+        &self,
+        request: Request<NeuronRef>,
+    ) -> Result<Response<NeuronParameters>, Status> {
+        let NeuronRef { neuron_id } = request.into_inner();
+        self.neuron(&neuron_id)?
+            .get_parameters(Request::new(ParametersRequest {}))
+            .await
+    }
+
+    async fn list_neurons(
+        &self,
+        _request: Request<LayerNeuronsRequest>,
+    ) -> Result<Response<LayerNeuronsResponse>, Status> {
+        Ok(Response::new(LayerNeuronsResponse {
+            layer_id: self.id.clone(),
+            neuron_ids: self.neurons.iter().map(|neuron| neuron.id().to_string()).collect(),
+        }))
+    }
+}
@@ -0,0 +1,180 @@
+// learning.rs
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum LearningError {
+    #[error("BCM time constant must be positive, got {0}")]
+    InvalidTimeConstant(f32),
+}
+
+/// A local, unsupervised weight update computed from the pre-synaptic inputs
+/// and the post-synaptic activation. Returned deltas are unscaled; the caller
+/// multiplies them by the learning rate.
+pub trait LearningRule: Send + Debug {
+    fn deltas(&mut self, inputs: &[f32], output: f32, weights: &[f32]) -> Vec<f32>;
+    fn name(&self) -> &'static str;
+    /// Snapshot of what the rule adapts as it learns, persisted next to the
+    /// weights. Stateless rules keep the default.
+    fn state(&self) -> LearningRuleState {
+        LearningRuleState {
+            name: self.name().to_string(),
+            values: Vec::new(),
+        }
+    }
+    fn load_state(&mut self, _state: LearningRuleState) {}
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LearningRuleState {
+    pub name: String,
+    pub values: Vec<f32>,
+}
+
+/// Plain Hebbian rule: `dw_i = x_i * y`.
+#[derive(Debug)]
+pub struct Hebbian;
+
+impl LearningRule for Hebbian {
+    fn deltas(&mut self, inputs: &[f32], output: f32, _weights: &[f32]) -> Vec<f32> {
+        inputs.iter().map(|x| x * output).collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "hebbian"
+    }
+}
+
+/// Oja's rule: `dw_i = y * (x_i - y * w_i)`, which keeps the weight norm bounded.
+#[derive(Debug)]
+pub struct Oja;
+
+impl LearningRule for Oja {
+    fn deltas(&mut self, inputs: &[f32], output: f32, weights: &[f32]) -> Vec<f32> {
+        inputs
+            .iter()
+            .zip(weights)
+            .map(|(x, w)| output * (x - output * w))
+            .collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "oja"
+    }
+}
+
+/// Bienenstock-Cooper-Munro rule: `dw_i = x_i * y * (y - theta)`, where the
+/// modification threshold `theta` tracks the running mean of `y^2`.
+#[derive(Debug)]
+pub struct Bcm {
+    theta: f32,
+    time_constant: f32,
+}
+
+impl Bcm {
+    /// Fails unless `time_constant`, the number of outputs `theta` averages
+    /// over, is positive.
+    pub fn new(initial_theta: f32, time_constant: f32) -> Result<Self, LearningError> {
+        if time_constant <= 0.0 || time_constant.is_nan() {
+            return Err(LearningError::InvalidTimeConstant(time_constant));
+        }
+        Ok(Self {
+            theta: initial_theta,
+            time_constant,
+        })
+    }
+
+    pub fn theta(&self) -> f32 {
+        self.theta
+    }
+}
+
+impl LearningRule for Bcm {
+    fn deltas(&mut self, inputs: &[f32], output: f32, _weights: &[f32]) -> Vec<f32> {
+        let deltas = inputs
+            .iter()
+            .map(|x| x * output * (output - self.theta))
+            .collect();
+        self.theta += (output * output - self.theta) / self.time_constant;
+        deltas
+    }
+
+    fn name(&self) -> &'static str {
+        "bcm"
+    }
+
+    fn state(&self) -> LearningRuleState {
+        LearningRuleState {
+            name: self.name().to_string(),
+            values: vec![self.theta],
+        }
+    }
+
+    fn load_state(&mut self, state: LearningRuleState) {
+        if let Some(&theta) = state.values.first() {
+            self.theta = theta;
+        }
+    }
+}
+
+pub fn from_name(name: &str) -> Option<Box<dyn LearningRule>> {
+    match name {
+        "hebbian" => Some(Box::new(Hebbian)),
+        "oja" => Some(Box::new(Oja)),
+        "bcm" => Some(Box::new(Bcm::new(0.0, 100.0).expect("Default BCM time constant is positive"))),
+        _ => None,
+    }
+}
+
+/// A learning rule attached to a neuron, with its learning rate and freeze switch.
+#[derive(Debug)]
+pub struct LocalLearning {
+    rule: Mutex<Box<dyn LearningRule>>,
+    learning_rate: f32,
+    frozen: AtomicBool,
+}
+
+impl LocalLearning {
+    pub fn new(rule: Box<dyn LearningRule>, learning_rate: f32) -> Self {
+        Self {
+            rule: Mutex::new(rule),
+            learning_rate,
+            frozen: AtomicBool::new(false),
+        }
+    }
+
+    pub fn deltas(&self, inputs: &[f32], output: f32, weights: &[f32]) -> Vec<f32> {
+        let mut rule = self.rule.lock().expect("Learning rule lock poisoned");
+        rule.deltas(inputs, output, weights)
+            .into_iter()
+            .map(|delta| delta * self.learning_rate)
+            .collect()
+    }
+
+    pub fn state(&self) -> LearningRuleState {
+        self.rule.lock().expect("Learning rule lock poisoned").state()
+    }
+
+    pub fn load_state(&self, state: LearningRuleState) {
+        self.rule.lock().expect("Learning rule lock poisoned").load_state(state);
+    }
+
+    pub fn rule_name(&self) -> &'static str {
+        self.rule.lock().expect("Learning rule lock poisoned").name()
+    }
+
+    pub fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Relaxed)
+    }
+
+    pub fn set_frozen(&self, frozen: bool) {
+        self.frozen.store(frozen, Ordering::Relaxed);
+    }
+}
@@ -1,35 +1,83 @@
 // main.rs
 mod activation;
+mod alerting;
 mod database;
 mod extensions;
+mod health;
+mod input_schema;
+mod layer;
+mod learning;
 mod messenger_api_client;
+mod metrics_exporter;
+mod metrics_history;
+mod neuromodulation;
 mod neuron;
+mod neuron_metrics;
+mod neuron_state;
+mod optimizer;
+mod process_manager;
 mod proto;
+mod registry;
+mod reporter;
+mod shutdown;
+mod spiking;
+mod stdp;
+mod synapse;
 mod supervisor;
+mod supervisor_state;
 mod telegram_bot;
+mod telemetry;
+mod topology;
 mod weight_init;
 
 use activation::ReLU;
 use extensions::{EyeExt, WebhookStreamExt, MessengerInExt, MessengerOutExt};
+use input_schema::{ExtensionPolicy, InputSchema};
+use layer::Layer;
 use messenger_api_client::MessengerApiClient;
-use neuron::Neuron;
+use metrics_history::MetricsHistory;
+use neuromodulation::ModulationBinding;
+use neuron::{Neuron, ResizePolicy, SharedNeuron};
+use neuron_state::ExtensionTokens;
+use optimizer::OptimizerConfig;
+use spiking::LifConfig;
+use stdp::StdpConfig;
+use synapse::SynapseConfig;
+use proto::layer_service_server::LayerServiceServer;
 use proto::neuron_service_server::NeuronServiceServer;
+use std::collections::HashMap;
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use supervisor::Supervisor;
 use telegram_bot::TelegramBot;
-use tokio::sync::mpsc;
+use topology::Topology;
+use tokio::sync::{mpsc, watch};
 use tonic::transport::Server;
 use weight_init::XavierUniform;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
+    telemetry::init("neurox")?;
 
+    // Neurons the supervisor runs as child processes serve their neuron and
+    // nothing else: no layer, supervisor, Telegram bot or messenger.
+    let neuron_only = env::var("NEURON_ONLY").is_ok();
     let neuron_id = env::var("NEURON_ID").unwrap_or_else(|_| "neuron_1".to_string());
+    let supervisor_endpoint = reporter::supervisor_endpoint();
     let num_inputs = env::var("NUM_INPUTS")
         .unwrap_or_else(|_| "10".to_string())
         .parse()
         .unwrap();
+    let input_schema = match env::var("NUM_EXTENSION_INPUTS") {
+        Ok(num_extension_inputs) => InputSchema::extended(
+            num_inputs,
+            num_extension_inputs.parse().unwrap(),
+            ExtensionPolicy::PadAndTruncate,
+        ),
+        Err(_) => InputSchema::fixed(num_inputs),
+    };
     let activation = Box::new(ReLU);
     let weight_initializer = XavierUniform;
 
@@ -42,69 +90,305 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (messenger_in_ext_sender, messenger_in_ext_receiver) = mpsc::channel(32);
     let messenger_in_ext = Some(MessengerInExt::new(messenger_in_ext_sender));
 
-    let messenger_api_client = MessengerApiClient::new(
-        env::var("MESSENGER_API_TOKEN").expect("MESSENGER_API_TOKEN not set"),
-    );
-    let messenger_out_ext = Some(MessengerOutExt::new(messenger_api_client));
+    let messenger_out_ext = if neuron_only {
+        None
+    } else {
+        let messenger_api_client = MessengerApiClient::new(
+            env::var("MESSENGER_API_TOKEN").expect("MESSENGER_API_TOKEN not set"),
+        );
+        Some(MessengerOutExt::new(messenger_api_client))
+    };
 
     let (extension_sender, extension_receiver) = mpsc::channel(32);
+    let extensions_alive = Arc::new(AtomicBool::new(true));
+    let forwarder_alive = extensions_alive.clone();
     tokio::spawn(async move {
         tokio::select! {
             _ = handle_eye_ext(eye_ext_receiver, extension_sender.clone()) => {}
             _ = handle_webhook_ext(webhook_ext_receiver, extension_sender.clone()) => {}
         }
+        log::error!("Extension receivers closed");
+        forwarder_alive.store(false, Ordering::Relaxed);
     });
 
-    let neuron = Neuron::new(
-        neuron_id,
-        num_inputs,
+    let resize_policy = match env::var("RESIZE_POLICY").as_deref() {
+        Ok("resize") => ResizePolicy::Resize,
+        Ok("reinitialize") => ResizePolicy::Reinitialize,
+        _ => ResizePolicy::Refuse,
+    };
+
+    let neuron = Neuron::open(
+        neuron_id.clone(),
+        input_schema,
         activation,
         &weight_initializer,
+        resize_policy,
         eye_ext,
         webhook_ext,
         messenger_in_ext,
         messenger_out_ext,
         Some(extension_receiver),
-    );
+    )?;
+    let neuron = match env::var("LEARNING_RULE") {
+        Ok(rule) => {
+            let rule = learning::from_name(&rule).expect("Unknown LEARNING_RULE");
+            let learning_rate = env::var("LEARNING_RATE")
+                .unwrap_or_else(|_| "0.01".to_string())
+                .parse()
+                .unwrap();
+            neuron.with_learning_rule(rule, learning_rate)?
+        }
+        Err(_) => neuron,
+    };
+    let neuron = match env::var("OPTIMIZER") {
+        Ok(config) => {
+            let config: OptimizerConfig = serde_json::from_str(&config).expect("Invalid OPTIMIZER config");
+            neuron.with_optimizer(config.build())?
+        }
+        Err(_) => neuron,
+    };
+    let neuron = match env::var("SPIKING") {
+        Ok(config) => {
+            let config: LifConfig = serde_json::from_str(&config).expect("Invalid SPIKING config");
+            neuron.with_spiking(config)?
+        }
+        Err(_) => neuron,
+    };
+    let neuron = match env::var("STDP") {
+        Ok(config) => {
+            let config: StdpConfig = serde_json::from_str(&config).expect("Invalid STDP config");
+            neuron.with_stdp(config)?
+        }
+        Err(_) => neuron,
+    };
+    let neuron = match env::var("NEUROMODULATION") {
+        Ok(bindings) => {
+            let bindings: Vec<ModulationBinding> =
+                serde_json::from_str(&bindings).expect("Invalid NEUROMODULATION bindings");
+            let (levels_sender, levels_receiver) = watch::channel(HashMap::new());
+            tokio::spawn(neuromodulation::follow_supervisor(
+                supervisor_endpoint.clone(),
+                neuron_id.clone(),
+                levels_sender,
+            ));
+            neuron.with_neuromodulation(bindings, levels_receiver)
+        }
+        Err(_) => neuron,
+    };
+    let neuron = match env::var("SYNAPSES") {
+        Ok(config) => {
+            let config: SynapseConfig = serde_json::from_str(&config).expect("Invalid SYNAPSES config");
+            neuron.with_synapses(config)?
+        }
+        Err(_) => neuron,
+    };
 
     let neuron_addr = env::var("NEURON_ADDR").unwrap_or_else(|_| "[::1]:50051".to_string());
+    let neuron_endpoint = env::var("NEURON_ENDPOINT").unwrap_or_else(|_| format!("http://{}", neuron_addr));
     let neuron_addr = neuron_addr.parse().unwrap();
+    let (neuron_shutdown_sender, neuron_shutdown) = watch::channel(false);
+    let (supervisor_shutdown_sender, supervisor_shutdown) = watch::channel(false);
+    let shutdown_deadline = env::var("SHUTDOWN_DEADLINE_SECS")
+        .map(|secs| Duration::from_secs(secs.parse().expect("Invalid SHUTDOWN_DEADLINE_SECS")))
+        .unwrap_or(shutdown::DEFAULT_DEADLINE);
+
+    let (registered_sender, registered) = watch::channel(false);
+    let registration = tokio::spawn(registry::maintain_registration(
+        supervisor_endpoint.clone(),
+        neuron.registration(neuron_endpoint),
+        registered_sender,
+    ));
 
+    let metrics_interval = env::var("METRICS_INTERVAL_SECS")
+        .map(|secs| Duration::from_secs(secs.parse().expect("Invalid METRICS_INTERVAL_SECS")))
+        .unwrap_or(neuron_metrics::DEFAULT_REPORT_INTERVAL);
+    let neuron = Arc::new(neuron);
+    Neuron::spawn_metrics_reporting(&neuron, metrics_interval);
+    if let Ok(neuron_metrics_addr) = env::var("NEURON_METRICS_ADDR") {
+        let neuron = neuron.clone();
+        metrics_exporter::spawn_exporter(neuron_metrics_addr.parse().unwrap(), move || {
+            vec![neuron.metrics_snapshot()]
+        });
+    }
+
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let ready_neuron = neuron.clone();
+    tokio::spawn(health::report_readiness::<NeuronServiceServer<SharedNeuron>, _>(
+        health_reporter,
+        move || ready_neuron.is_ready() && *registered.borrow() && extensions_alive.load(Ordering::Relaxed),
+    ));
+
+    let serving_neuron = neuron.clone();
+    let server_shutdown = neuron_shutdown.clone();
     tokio::spawn(async move {
         Server::builder()
-            .add_service(NeuronServiceServer::new(neuron))
-            .serve(neuron_addr)
+            .add_service(health_service)
+            .add_service(health::reflection_service())
+            .add_service(NeuronServiceServer::new(SharedNeuron::new(serving_neuron)))
+            .serve_with_shutdown(neuron_addr, shutdown::wait(server_shutdown))
             .await
             .unwrap();
     });
 
-    let (neuron_status_sender, neuron_status_receiver) = mpsc::channel(100);
+    if neuron_only {
+        shutdown::signal().await;
+        log::info!("Shutting down within {}s", shutdown_deadline.as_secs());
+        let graceful = async {
+            let _ = neuron_shutdown_sender.send(true);
+            neuron.shutdown().await;
+            registration.abort();
+            if let Err(e) = registry::deregister(supervisor_endpoint.clone(), neuron_id.clone()).await {
+                log::warn!("Failed to deregister Neuron {}: {}", neuron_id, e);
+            }
+        };
+        if tokio::time::timeout(shutdown_deadline, graceful).await.is_err() {
+            log::warn!("Shutdown deadline of {}s exceeded, exiting", shutdown_deadline.as_secs());
+        }
+        telemetry::shutdown();
+        return Ok(());
+    }
+
+    let mut layer_neurons = Vec::new();
+    if let Ok(layer_size) = env::var("LAYER_SIZE") {
+        let layer_id = env::var("LAYER_ID").unwrap_or_else(|_| "layer_1".to_string());
+        let layer_activation = env::var("LAYER_ACTIVATION").unwrap_or_else(|_| "relu".to_string());
+        let layer = Layer::open(
+            layer_id,
+            layer_size.parse().unwrap(),
+            InputSchema::fixed(num_inputs),
+            &layer_activation,
+            &weight_initializer,
+            resize_policy,
+        )?;
+        layer.spawn_metrics_reporting(metrics_interval);
+        if let Ok(layer_metrics_addr) = env::var("LAYER_METRICS_ADDR") {
+            let neurons = layer.neurons();
+            metrics_exporter::spawn_exporter(layer_metrics_addr.parse().unwrap(), move || {
+                neurons.iter().map(|neuron| neuron.metrics_snapshot()).collect()
+            });
+        }
+        let layer_addr = env::var("LAYER_ADDR").unwrap_or_else(|_| "[::1]:50053".to_string());
+        let layer_addr = layer_addr.parse().unwrap();
+
+        let (health_reporter, health_service) = tonic_health::server::health_reporter();
+        layer_neurons = layer.neurons();
+        let neurons = layer.neurons();
+        tokio::spawn(health::report_readiness::<LayerServiceServer<Layer>, _>(health_reporter, move || {
+            neurons.iter().all(|neuron| neuron.is_ready())
+        }));
+
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(health_service)
+                .add_service(health::reflection_service())
+                .add_service(LayerServiceServer::new(layer))
+                .serve_with_shutdown(layer_addr, shutdown::wait(neuron_shutdown))
+                .await
+                .unwrap();
+        });
+    }
+
     let (telegram_bot_sender, telegram_bot_receiver) = mpsc::channel(100);
 
-    let supervisor = Supervisor::new(neuron_status_sender, telegram_bot_sender);
+    let metrics_retention = env::var("METRICS_RETENTION_SECS")
+        .map(|secs| Duration::from_secs(secs.parse().expect("Invalid METRICS_RETENTION_SECS")))
+        .unwrap_or(metrics_history::DEFAULT_RETENTION);
+    let mut supervisor = Supervisor::new(telegram_bot_sender)
+        .with_metrics_history(MetricsHistory::new(
+            metrics_retention,
+            metrics_history::DEFAULT_MAX_SAMPLES,
+        ))
+        .with_stop_deadline(shutdown_deadline);
+    if let Ok(topology_file) = env::var("TOPOLOGY_FILE") {
+        let topology = Topology::load(&topology_file)?;
+        let plan = supervisor.apply_topology(topology)?;
+        log::info!(
+            "Applied topology {}: started {:?}, stopped {:?}, restarted {:?}",
+            topology_file,
+            plan.start,
+            plan.stop,
+            plan.restart
+        );
+    }
+
+    if let Ok(alert_rules_file) = env::var("ALERT_RULES_FILE") {
+        let rules = alerting::load_rules(&alert_rules_file)?;
+        log::info!("Loaded {} alert rules from {}", rules.len(), alert_rules_file);
+        supervisor = supervisor.with_alert_rules(rules);
+    }
+
+    let supervisor_metrics_addr =
+        env::var("SUPERVISOR_METRICS_ADDR").unwrap_or_else(|_| "[::1]:9090".to_string());
+    supervisor.serve_metrics(supervisor_metrics_addr.parse().unwrap());
+    supervisor.watch_leases();
+    supervisor.watch_processes();
+    supervisor.watch_alerts();
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    supervisor.watch_health(health_reporter);
+    let supervisor_stopped = supervisor.watch_shutdown(supervisor_shutdown);
 
     let supervisor_addr = env::var("SUPERVISOR_ADDR").unwrap_or_else(|_| "[::1]:50052".to_string());
     let supervisor_addr = supervisor_addr.parse().unwrap();
 
-    tokio::spawn(async move {
+    // Serves until the managed neurons have stopped, so that they can still
+    // report and deregister.
+    let supervisor_server = tokio::spawn(async move {
         Server::builder()
+            .add_service(health_service)
+            .add_service(health::reflection_service())
             .add_service(supervisor::SupervisorServer::new(supervisor))
-            .serve(supervisor_addr)
+            .serve_with_shutdown(supervisor_addr, async {
+                let _ = supervisor_stopped.await;
+            })
             .await
             .unwrap();
     });
 
     let telegram_token = env::var("TELEGRAM_BOT_TOKEN").expect("TELEGRAM_BOT_TOKEN not set");
-    let telegram_bot = TelegramBot::new(telegram_token, telegram_bot_receiver).unwrap();
+    let mut telegram_bot = TelegramBot::new(telegram_token, telegram_bot_receiver).unwrap();
 
-    telegram_bot.run().await;
+    // The bot keeps running during shutdown so that it can still announce it.
+    let telegram = telegram_bot.run();
+    tokio::pin!(telegram);
+    let mut telegram_running = true;
+    tokio::select! {
+        _ = &mut telegram => {
+            log::warn!("Telegram bot stopped");
+            telegram_running = false;
+        }
+        _ = shutdown::signal() => {}
+    }
+    log::info!("Shutting down within {}s", shutdown_deadline.as_secs());
+
+    // Neurons go first, while the supervisor can still take their last reports.
+    let graceful = async {
+        let _ = neuron_shutdown_sender.send(true);
+        for neuron in std::iter::once(&neuron).chain(&layer_neurons) {
+            neuron.shutdown().await;
+        }
+        registration.abort();
+        if let Err(e) = registry::deregister(supervisor_endpoint.clone(), neuron_id.clone()).await {
+            log::warn!("Failed to deregister Neuron {}: {}", neuron_id, e);
+        }
+        let _ = supervisor_shutdown_sender.send(true);
+        let _ = supervisor_server.await;
+    };
+    tokio::select! {
+        result = tokio::time::timeout(shutdown_deadline, graceful) => {
+            if result.is_err() {
+                log::warn!("Shutdown deadline of {}s exceeded, exiting", shutdown_deadline.as_secs());
+            }
+        }
+        _ = &mut telegram, if telegram_running => {}
+    }
+    telemetry::shutdown();
 
     Ok(())
 }
 
 async fn handle_eye_ext(
     mut eye_ext_receiver: mpsc::Receiver<Vec<u8>>,
-    extension_sender: mpsc::Sender<Vec<f32>>,
+    extension_sender: mpsc::Sender<ExtensionTokens>,
 ) {
     while let Some(image_data) = eye_ext_receiver.recv().await {
         let eye_ext = EyeExt::new(extension_sender.clone());
@@ -116,7 +400,7 @@ async fn handle_eye_ext(
 
 async fn handle_webhook_ext(
     mut webhook_ext_receiver: mpsc::Receiver<String>,
-    extension_sender: mpsc::Sender<Vec<f32>>,
+    extension_sender: mpsc::Sender<ExtensionTokens>,
 ) {
     while let Some(json_data) = webhook_ext_receiver.recv().await {
         let webhook_ext = WebhookStreamExt::new(extension_sender.clone());
@@ -0,0 +1,120 @@
+// metrics_exporter.rs
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+const PREFIX: &str = "neurox";
+
+/// Everything exported about one neuron.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NeuronSnapshot {
+    pub neuron_id: String,
+    pub status: Option<String>,
+    pub metrics: HashMap<String, f64>,
+}
+
+/// Renders `neurons` in the OpenMetrics text format: one gauge family per
+/// metric name labeled by `neuron_id`, plus `neurox_neuron_status` set to 1
+/// for each neuron's current status.
+pub fn render(neurons: &[NeuronSnapshot]) -> String {
+    let mut families: BTreeMap<String, Vec<(&str, f64)>> = BTreeMap::new();
+    for neuron in neurons {
+        for (name, value) in &neuron.metrics {
+            families
+                .entry(metric_name(name))
+                .or_default()
+                .push((&neuron.neuron_id, *value));
+        }
+    }
+
+    let mut output = String::new();
+    for (name, samples) in &mut families {
+        samples.sort_by(|a, b| a.0.cmp(b.0));
+        let _ = writeln!(output, "# TYPE {} gauge", name);
+        for (neuron_id, value) in samples.iter() {
+            let _ = writeln!(output, "{}{{neuron_id=\"{}\"}} {}", name, escape(neuron_id), value);
+        }
+    }
+
+    let mut statuses: Vec<(&str, &str)> = neurons
+        .iter()
+        .filter_map(|neuron| neuron.status.as_deref().map(|status| (neuron.neuron_id.as_str(), status)))
+        .collect();
+    if !statuses.is_empty() {
+        statuses.sort();
+        let _ = writeln!(output, "# TYPE {}_neuron_status gauge", PREFIX);
+        for (neuron_id, status) in statuses {
+            let _ = writeln!(
+                output,
+                "{}_neuron_status{{neuron_id=\"{}\",status=\"{}\"}} 1",
+                PREFIX,
+                escape(neuron_id),
+                escape(status)
+            );
+        }
+    }
+    output.push_str("# EOF\n");
+    output
+}
+
+/// Maps a neuron metric name onto a valid, prefixed OpenMetrics name.
+fn metric_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+    format!("{}_{}", PREFIX, sanitized)
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Serves `GET /metrics` on `addr`, rendering a fresh snapshot per scrape.
+pub fn spawn_exporter<F>(addr: SocketAddr, snapshot: F) -> JoinHandle<()>
+where
+    F: Fn() -> Vec<NeuronSnapshot> + Send + Sync + 'static,
+{
+    let snapshot = Arc::new(snapshot);
+    tokio::spawn(async move {
+        let make_service = make_service_fn(move |_| {
+            let snapshot = snapshot.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let snapshot = snapshot.clone();
+                    async move { Ok::<_, Infallible>(respond(&request, snapshot.as_ref())) }
+                }))
+            }
+        });
+        match Server::try_bind(&addr) {
+            Ok(server) => {
+                log::info!("Serving metrics on http://{}/metrics", addr);
+                if let Err(e) = server.serve(make_service).await {
+                    log::error!("Metrics exporter error: {}", e);
+                }
+            }
+            Err(e) => log::error!("Failed to bind metrics exporter to {}: {}", addr, e),
+        }
+    })
+}
+
+fn respond(request: &Request<Body>, snapshot: &dyn Fn() -> Vec<NeuronSnapshot>) -> Response<Body> {
+    if request.uri().path() != "/metrics" {
+        let mut response = Response::new(Body::from("Not found\n"));
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return response;
+    }
+    Response::builder()
+        .header(hyper::header::CONTENT_TYPE, CONTENT_TYPE)
+        .body(Body::from(render(&snapshot())))
+        .expect("Static response parts are valid")
+}
@@ -0,0 +1,141 @@
+// metrics_history.rs
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Duration;
+
+pub const DEFAULT_RETENTION: Duration = Duration::from_secs(3600);
+pub const DEFAULT_MAX_SAMPLES: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub timestamp_ms: u64,
+    pub value: f64,
+}
+
+/// Summary of the samples of one metric that fall into `[start_ms, start_ms + bucket_ms)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub start_ms: u64,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub count: u32,
+}
+
+impl Bucket {
+    fn new(start_ms: u64, value: f64) -> Self {
+        Self {
+            start_ms,
+            min: value,
+            max: value,
+            avg: value,
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.count += 1;
+        self.avg += (value - self.avg) / self.count as f64;
+    }
+}
+
+/// Per-neuron, per-metric ring buffers of reported values. Samples older than
+/// `retention`, or beyond `max_samples` per series, are dropped oldest first.
+#[derive(Debug)]
+pub struct MetricsHistory {
+    retention_ms: u64,
+    max_samples: usize,
+    series: HashMap<String, HashMap<String, VecDeque<Sample>>>,
+}
+
+impl MetricsHistory {
+    pub fn new(retention: Duration, max_samples: usize) -> Self {
+        Self {
+            retention_ms: retention.as_millis() as u64,
+            max_samples,
+            series: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, neuron_id: &str, metrics: &HashMap<String, f64>, timestamp_ms: u64) {
+        let cutoff = timestamp_ms.saturating_sub(self.retention_ms);
+        let neuron = self.series.entry(neuron_id.to_string()).or_default();
+        for (metric, value) in metrics {
+            let samples = neuron.entry(metric.clone()).or_default();
+            samples.push_back(Sample {
+                timestamp_ms,
+                value: *value,
+            });
+            while samples.len() > self.max_samples {
+                samples.pop_front();
+            }
+        }
+        // Also age out metrics the neuron stopped reporting.
+        age_out(neuron, cutoff);
+    }
+
+    /// Drops the samples that are older than the retention at `now_ms`,
+    /// including those of neurons that stopped reporting altogether.
+    pub fn prune(&mut self, now_ms: u64) {
+        let cutoff = now_ms.saturating_sub(self.retention_ms);
+        for neuron in self.series.values_mut() {
+            age_out(neuron, cutoff);
+        }
+        self.series.retain(|_, neuron| !neuron.is_empty());
+    }
+
+    /// Names of the metrics recorded for `neuron_id`, sorted.
+    pub fn metric_names(&self, neuron_id: &str) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .series
+            .get(neuron_id)
+            .map(|neuron| neuron.keys().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Samples of one metric in `[start_ms, end_ms]`, downsampled into buckets
+    /// of `bucket_ms` aligned to multiples of it. A `bucket_ms` of 0 returns
+    /// every sample as its own bucket.
+    pub fn query(&self, neuron_id: &str, metric: &str, start_ms: u64, end_ms: u64, bucket_ms: u64) -> Vec<Bucket> {
+        let samples = match self.series.get(neuron_id).and_then(|neuron| neuron.get(metric)) {
+            Some(samples) => samples,
+            None => return Vec::new(),
+        };
+        let in_range = samples
+            .iter()
+            .filter(|sample| sample.timestamp_ms >= start_ms && sample.timestamp_ms <= end_ms);
+        if bucket_ms == 0 {
+            return in_range
+                .map(|sample| Bucket::new(sample.timestamp_ms, sample.value))
+                .collect();
+        }
+
+        let mut buckets: BTreeMap<u64, Bucket> = BTreeMap::new();
+        for sample in in_range {
+            let bucket_start = sample.timestamp_ms - sample.timestamp_ms % bucket_ms;
+            buckets
+                .entry(bucket_start)
+                .and_modify(|bucket| bucket.add(sample.value))
+                .or_insert_with(|| Bucket::new(bucket_start, sample.value));
+        }
+        buckets.into_values().collect()
+    }
+}
+
+fn age_out(neuron: &mut HashMap<String, VecDeque<Sample>>, cutoff: u64) {
+    for samples in neuron.values_mut() {
+        while samples.front().map_or(false, |sample| sample.timestamp_ms < cutoff) {
+            samples.pop_front();
+        }
+    }
+    neuron.retain(|_, samples| !samples.is_empty());
+}
+
+impl Default for MetricsHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_RETENTION, DEFAULT_MAX_SAMPLES)
+    }
+}
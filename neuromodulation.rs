@@ -0,0 +1,102 @@
+// neuromodulation.rs
+use crate::proto::supervisor_client::SupervisorClient;
+use crate::proto::NeuromodulatorSubscription;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::watch;
+use tonic::Request;
+
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+pub type NeuromodulatorLevels = HashMap<String, f32>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModulationTarget {
+    LearningRate,
+    ActivationGain,
+    FiringThreshold,
+}
+
+/// Binds a named neuromodulator to a neuron quantity. The quantity is scaled
+/// by `max(0, 1 + sensitivity * (level - baseline))`, so an unset modulator
+/// (level 0) with `baseline = 0` leaves it unchanged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModulationBinding {
+    pub modulator: String,
+    pub target: ModulationTarget,
+    #[serde(default)]
+    pub baseline: f32,
+    #[serde(default = "default_sensitivity")]
+    pub sensitivity: f32,
+}
+
+fn default_sensitivity() -> f32 {
+    1.0
+}
+
+#[derive(Debug)]
+pub struct Neuromodulation {
+    bindings: Vec<ModulationBinding>,
+    levels: watch::Receiver<NeuromodulatorLevels>,
+}
+
+impl Neuromodulation {
+    pub fn new(bindings: Vec<ModulationBinding>, levels: watch::Receiver<NeuromodulatorLevels>) -> Self {
+        Self { bindings, levels }
+    }
+
+    /// Combined scale factor of all bindings for `target`.
+    pub fn factor(&self, target: ModulationTarget) -> f32 {
+        let levels = self.levels.borrow();
+        self.bindings
+            .iter()
+            .filter(|binding| binding.target == target)
+            .map(|binding| {
+                let level = levels.get(&binding.modulator).copied().unwrap_or(0.0);
+                (1.0 + binding.sensitivity * (level - binding.baseline)).max(0.0)
+            })
+            .product()
+    }
+}
+
+/// Keeps `sender` in sync with the supervisor's neuromodulator broadcast,
+/// resubscribing whenever the stream ends or the supervisor is unreachable.
+pub async fn follow_supervisor(
+    supervisor_endpoint: String,
+    neuron_id: String,
+    sender: watch::Sender<NeuromodulatorLevels>,
+) {
+    loop {
+        match SupervisorClient::connect(supervisor_endpoint.clone()).await {
+            Ok(mut client) => {
+                let request = Request::new(NeuromodulatorSubscription {
+                    neuron_id: neuron_id.clone(),
+                });
+                match client.subscribe_neuromodulators(request).await {
+                    Ok(response) => {
+                        let mut stream = response.into_inner();
+                        loop {
+                            match stream.message().await {
+                                Ok(Some(levels)) => {
+                                    if sender.send(levels.levels).is_err() {
+                                        return;
+                                    }
+                                }
+                                Ok(None) => break,
+                                Err(e) => {
+                                    log::warn!("Neuromodulator stream error: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to subscribe to neuromodulators: {}", e),
+                }
+            }
+            Err(e) => log::warn!("Failed to connect to supervisor for neuromodulators: {}", e),
+        }
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
@@ -0,0 +1,143 @@
+// neuron_metrics.rs
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// Latencies kept per window for percentiles; older ones are dropped first.
+const LATENCY_SAMPLE_CAPACITY: usize = 4096;
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    latencies: VecDeque<f64>,
+    outputs: u64,
+    activation_mean: f64,
+    activation_m2: f64,
+    dead_outputs: u64,
+    extension_tokens: u64,
+    request_count: u64,
+    error_count: u64,
+}
+
+impl Window {
+    fn new(request_count: u64, error_count: u64) -> Self {
+        Self {
+            started: Instant::now(),
+            latencies: VecDeque::new(),
+            outputs: 0,
+            activation_mean: 0.0,
+            activation_m2: 0.0,
+            dead_outputs: 0,
+            extension_tokens: 0,
+            request_count,
+            error_count,
+        }
+    }
+}
+
+/// Totals and gauges read from the neuron when a window is closed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NeuronTotals {
+    pub request_count: u64,
+    pub error_count: u64,
+    pub weight_norm: f64,
+    pub last_update_ms: u64,
+}
+
+/// Aggregates what a neuron does between two reports to the supervisor.
+#[derive(Debug)]
+pub struct NeuronMetrics {
+    window: Mutex<Window>,
+}
+
+impl NeuronMetrics {
+    pub fn new() -> Self {
+        Self {
+            window: Mutex::new(Window::new(0, 0)),
+        }
+    }
+
+    pub fn record_output(&self, latency: Duration, activation: f32) {
+        let mut window = self.window.lock().expect("Metrics window lock poisoned");
+        if window.latencies.len() == LATENCY_SAMPLE_CAPACITY {
+            window.latencies.pop_front();
+        }
+        window.latencies.push_back(latency.as_secs_f64());
+
+        // Welford's online mean and variance.
+        let activation = activation as f64;
+        window.outputs += 1;
+        let delta = activation - window.activation_mean;
+        window.activation_mean += delta / window.outputs as f64;
+        window.activation_m2 += delta * (activation - window.activation_mean);
+        if activation == 0.0 {
+            window.dead_outputs += 1;
+        }
+    }
+
+    pub fn record_extension_tokens(&self, count: usize) {
+        let mut window = self.window.lock().expect("Metrics window lock poisoned");
+        window.extension_tokens += count as u64;
+    }
+
+    /// Summarizes the current window and starts a new one.
+    pub fn take(&self, totals: NeuronTotals) -> HashMap<String, f64> {
+        let window = {
+            let mut window = self.window.lock().expect("Metrics window lock poisoned");
+            std::mem::replace(&mut *window, Window::new(totals.request_count, totals.error_count))
+        };
+        let window_secs = window.started.elapsed().as_secs_f64();
+
+        let mut metrics = HashMap::new();
+        metrics.insert("window_secs".to_string(), window_secs);
+        metrics.insert("request_count".to_string(), totals.request_count as f64);
+        metrics.insert("error_count".to_string(), totals.error_count as f64);
+        metrics.insert(
+            "requests".to_string(),
+            totals.request_count.saturating_sub(window.request_count) as f64,
+        );
+        metrics.insert(
+            "errors".to_string(),
+            totals.error_count.saturating_sub(window.error_count) as f64,
+        );
+        metrics.insert("weight_norm".to_string(), totals.weight_norm);
+        metrics.insert("last_update_ms".to_string(), totals.last_update_ms as f64);
+        if window_secs > 0.0 {
+            metrics.insert(
+                "extension_tokens_per_sec".to_string(),
+                window.extension_tokens as f64 / window_secs,
+            );
+        }
+
+        if window.outputs > 0 {
+            let mut latencies: Vec<f64> = window.latencies.into_iter().collect();
+            latencies.sort_by(|a, b| a.partial_cmp(b).expect("Latency is not NaN"));
+            for (name, quantile) in &[("latency_p50", 0.5), ("latency_p90", 0.9), ("latency_p99", 0.99)] {
+                metrics.insert(name.to_string(), percentile(&latencies, *quantile));
+            }
+            metrics.insert("activation_mean".to_string(), window.activation_mean);
+            metrics.insert(
+                "activation_variance".to_string(),
+                window.activation_m2 / window.outputs as f64,
+            );
+            metrics.insert(
+                "dead_fraction".to_string(),
+                window.dead_outputs as f64 / window.outputs as f64,
+            );
+        }
+        metrics
+    }
+}
+
+impl Default for NeuronMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`.
+fn percentile(values: &[f64], quantile: f64) -> f64 {
+    let rank = (quantile * values.len() as f64).ceil() as usize;
+    values[rank.saturating_sub(1).min(values.len() - 1)]
+}
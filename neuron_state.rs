@@ -0,0 +1,168 @@
+// neuron_state.rs
+use crate::spiking;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::{mpsc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+const FORWARD_CACHE_CAPACITY: usize = 1024;
+
+/// Learnable parameters of a neuron. `version` is bumped on every update so
+/// outputs can be traced back to the weights that produced them.
+#[derive(Debug, Clone)]
+pub struct Parameters {
+    pub weights: Vec<f32>,
+    pub bias: f32,
+    pub version: u64,
+    /// When this process last updated the parameters; 0 if it has not.
+    pub updated_at_ms: u64,
+}
+
+impl Parameters {
+    pub fn new(weights: Vec<f32>, bias: f32) -> Self {
+        Self {
+            weights,
+            bias,
+            version: 0,
+            updated_at_ms: 0,
+        }
+    }
+
+    pub fn apply_deltas(&mut self, deltas: &[f32], bias_delta: f32) {
+        for (weight, delta) in self.weights.iter_mut().zip(deltas) {
+            *weight += delta;
+        }
+        self.bias += bias_delta;
+        self.version += 1;
+        self.updated_at_ms = spiking::now_ms();
+    }
+
+    pub fn weight_norm(&self) -> f64 {
+        self.weights
+            .iter()
+            .map(|weight| (*weight as f64).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    }
+}
+
+/// Inputs, weights, pre-activation and activation gain of one forward pass,
+/// kept for its `Backward` call.
+#[derive(Debug, Clone)]
+pub struct ForwardPass {
+    pub inputs: Vec<f32>,
+    /// The weights the output was computed with, which later updates may
+    /// already have replaced.
+    pub weights: Vec<f32>,
+    pub z: f32,
+    pub gain: f32,
+}
+
+/// Forward passes keyed by request id, evicting the oldest beyond `capacity`.
+#[derive(Debug)]
+pub struct ForwardCache {
+    capacity: usize,
+    passes: HashMap<String, ForwardPass>,
+    order: VecDeque<String>,
+}
+
+impl ForwardCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            passes: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn insert(&mut self, request_id: String, pass: ForwardPass) {
+        if self.passes.insert(request_id.clone(), pass).is_none() {
+            self.order.push_back(request_id);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.passes.remove(&oldest);
+            }
+        }
+    }
+
+    pub fn take(&mut self, request_id: &str) -> Option<ForwardPass> {
+        let pass = self.passes.remove(request_id)?;
+        self.order.retain(|id| id != request_id);
+        Some(pass)
+    }
+}
+
+/// Tokens an extension produced while handling one request, tagged with that
+/// request's id so the neuron consuming them can be correlated with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionTokens {
+    pub request_id: String,
+    pub tokens: Vec<f32>,
+}
+
+/// Mutable neuron state shared between the `&self` gRPC handlers.
+///
+/// Readers (`ProcessInput`) share the parameter lock; writers (`UpdateWeights`)
+/// take it exclusively, so an output never mixes weights from two versions.
+pub struct NeuronState {
+    parameters: RwLock<Parameters>,
+    extension_receiver: Mutex<Option<mpsc::Receiver<ExtensionTokens>>>,
+    forward_cache: Mutex<ForwardCache>,
+    request_count: AtomicU64,
+    error_count: AtomicU64,
+}
+
+impl NeuronState {
+    pub fn new(parameters: Parameters, extension_receiver: Option<mpsc::Receiver<ExtensionTokens>>) -> Self {
+        Self {
+            parameters: RwLock::new(parameters),
+            extension_receiver: Mutex::new(extension_receiver),
+            forward_cache: Mutex::new(ForwardCache::new(FORWARD_CACHE_CAPACITY)),
+            request_count: AtomicU64::new(0),
+            error_count: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn parameters(&self) -> RwLockReadGuard<'_, Parameters> {
+        self.parameters.read().await
+    }
+
+    pub async fn parameters_mut(&self) -> RwLockWriteGuard<'_, Parameters> {
+        self.parameters.write().await
+    }
+
+    /// Drains the extension tokens queued since the last call without waiting for more.
+    pub async fn drain_extension_tokens(&self) -> Vec<ExtensionTokens> {
+        let mut input_tokens = Vec::new();
+        if let Some(receiver) = self.extension_receiver.lock().await.as_mut() {
+            while let Ok(tokens) = receiver.try_recv() {
+                input_tokens.push(tokens);
+            }
+        }
+        input_tokens
+    }
+
+    pub async fn cache_forward_pass(&self, request_id: String, pass: ForwardPass) {
+        self.forward_cache.lock().await.insert(request_id, pass);
+    }
+
+    pub async fn take_forward_pass(&self, request_id: &str) -> Option<ForwardPass> {
+        self.forward_cache.lock().await.take(request_id)
+    }
+
+    pub fn record_request(&self) {
+        self.request_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.error_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn request_count(&self) -> u64 {
+        self.request_count.load(Ordering::Relaxed)
+    }
+
+    pub fn error_count(&self) -> u64 {
+        self.error_count.load(Ordering::Relaxed)
+    }
+}
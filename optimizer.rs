@@ -0,0 +1,260 @@
+// optimizer.rs
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+/// Turns gradients into parameter deltas. Parameters are passed flattened as
+/// `[weights..., bias]`.
+pub trait Optimizer: Send + Debug {
+    fn deltas(&mut self, gradients: &[f32]) -> Vec<f32>;
+    fn name(&self) -> &'static str;
+    /// Snapshot of the moment buffers, persisted next to the weights.
+    fn state(&self) -> OptimizerState;
+    fn load_state(&mut self, state: OptimizerState);
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OptimizerState {
+    pub name: String,
+    pub step: u64,
+    pub buffers: Vec<Vec<f32>>,
+}
+
+/// Per-neuron optimizer choice and hyperparameters, e.g.
+/// `{"type": "adam", "learning_rate": 0.001}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OptimizerConfig {
+    Sgd {
+        learning_rate: f32,
+        #[serde(default)]
+        momentum: f32,
+    },
+    Adam {
+        learning_rate: f32,
+        #[serde(default = "default_beta1")]
+        beta1: f32,
+        #[serde(default = "default_beta2")]
+        beta2: f32,
+        #[serde(default = "default_epsilon")]
+        epsilon: f32,
+    },
+    RmsProp {
+        learning_rate: f32,
+        #[serde(default = "default_decay")]
+        decay: f32,
+        #[serde(default = "default_epsilon")]
+        epsilon: f32,
+    },
+}
+
+fn default_beta1() -> f32 {
+    0.9
+}
+
+fn default_beta2() -> f32 {
+    0.999
+}
+
+fn default_decay() -> f32 {
+    0.9
+}
+
+fn default_epsilon() -> f32 {
+    1e-8
+}
+
+impl OptimizerConfig {
+    pub fn build(&self) -> Box<dyn Optimizer> {
+        match *self {
+            OptimizerConfig::Sgd {
+                learning_rate,
+                momentum,
+            } => Box::new(Sgd::new(learning_rate, momentum)),
+            OptimizerConfig::Adam {
+                learning_rate,
+                beta1,
+                beta2,
+                epsilon,
+            } => Box::new(Adam::new(learning_rate, beta1, beta2, epsilon)),
+            OptimizerConfig::RmsProp {
+                learning_rate,
+                decay,
+                epsilon,
+            } => Box::new(RmsProp::new(learning_rate, decay, epsilon)),
+        }
+    }
+}
+
+fn resize_buffer(buffer: &mut Vec<f32>, len: usize) {
+    if buffer.len() != len {
+        buffer.resize(len, 0.0);
+    }
+}
+
+/// Stochastic gradient descent with optional momentum:
+/// `v = momentum * v + g`, `delta = -lr * v`.
+#[derive(Debug)]
+pub struct Sgd {
+    learning_rate: f32,
+    momentum: f32,
+    velocity: Vec<f32>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f32, momentum: f32) -> Self {
+        Self {
+            learning_rate,
+            momentum,
+            velocity: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn deltas(&mut self, gradients: &[f32]) -> Vec<f32> {
+        resize_buffer(&mut self.velocity, gradients.len());
+        let (learning_rate, momentum) = (self.learning_rate, self.momentum);
+        self.velocity
+            .iter_mut()
+            .zip(gradients)
+            .map(|(v, g)| {
+                *v = momentum * *v + g;
+                -learning_rate * *v
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "sgd"
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState {
+            name: self.name().to_string(),
+            step: 0,
+            buffers: vec![self.velocity.clone()],
+        }
+    }
+
+    fn load_state(&mut self, state: OptimizerState) {
+        let mut buffers = state.buffers.into_iter();
+        self.velocity = buffers.next().unwrap_or_default();
+    }
+}
+
+/// Adam with bias-corrected first and second moment estimates.
+#[derive(Debug)]
+pub struct Adam {
+    learning_rate: f32,
+    beta1: f32,
+    beta2: f32,
+    epsilon: f32,
+    step: u64,
+    m: Vec<f32>,
+    v: Vec<f32>,
+}
+
+impl Adam {
+    pub fn new(learning_rate: f32, beta1: f32, beta2: f32, epsilon: f32) -> Self {
+        Self {
+            learning_rate,
+            beta1,
+            beta2,
+            epsilon,
+            step: 0,
+            m: Vec::new(),
+            v: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for Adam {
+    fn deltas(&mut self, gradients: &[f32]) -> Vec<f32> {
+        resize_buffer(&mut self.m, gradients.len());
+        resize_buffer(&mut self.v, gradients.len());
+        self.step += 1;
+        let m_correction = 1.0 - self.beta1.powi(self.step as i32);
+        let v_correction = 1.0 - self.beta2.powi(self.step as i32);
+
+        let mut deltas = Vec::with_capacity(gradients.len());
+        for ((m, v), g) in self.m.iter_mut().zip(self.v.iter_mut()).zip(gradients) {
+            *m = self.beta1 * *m + (1.0 - self.beta1) * g;
+            *v = self.beta2 * *v + (1.0 - self.beta2) * g * g;
+            let m_hat = *m / m_correction;
+            let v_hat = *v / v_correction;
+            deltas.push(-self.learning_rate * m_hat / (v_hat.sqrt() + self.epsilon));
+        }
+        deltas
+    }
+
+    fn name(&self) -> &'static str {
+        "adam"
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState {
+            name: self.name().to_string(),
+            step: self.step,
+            buffers: vec![self.m.clone(), self.v.clone()],
+        }
+    }
+
+    fn load_state(&mut self, state: OptimizerState) {
+        let mut buffers = state.buffers.into_iter();
+        self.step = state.step;
+        self.m = buffers.next().unwrap_or_default();
+        self.v = buffers.next().unwrap_or_default();
+    }
+}
+
+/// RMSProp: `s = decay * s + (1 - decay) * g^2`, `delta = -lr * g / (sqrt(s) + eps)`.
+#[derive(Debug)]
+pub struct RmsProp {
+    learning_rate: f32,
+    decay: f32,
+    epsilon: f32,
+    mean_square: Vec<f32>,
+}
+
+impl RmsProp {
+    pub fn new(learning_rate: f32, decay: f32, epsilon: f32) -> Self {
+        Self {
+            learning_rate,
+            decay,
+            epsilon,
+            mean_square: Vec::new(),
+        }
+    }
+}
+
+impl Optimizer for RmsProp {
+    fn deltas(&mut self, gradients: &[f32]) -> Vec<f32> {
+        resize_buffer(&mut self.mean_square, gradients.len());
+        let (learning_rate, decay, epsilon) = (self.learning_rate, self.decay, self.epsilon);
+        self.mean_square
+            .iter_mut()
+            .zip(gradients)
+            .map(|(s, g)| {
+                *s = decay * *s + (1.0 - decay) * g * g;
+                -learning_rate * g / (s.sqrt() + epsilon)
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &'static str {
+        "rms_prop"
+    }
+
+    fn state(&self) -> OptimizerState {
+        OptimizerState {
+            name: self.name().to_string(),
+            step: 0,
+            buffers: vec![self.mean_square.clone()],
+        }
+    }
+
+    fn load_state(&mut self, state: OptimizerState) {
+        let mut buffers = state.buffers.into_iter();
+        self.mean_square = buffers.next().unwrap_or_default();
+    }
+}
@@ -0,0 +1,475 @@
+// process_manager.rs
+use crate::registry::{self, NeuronRegistry};
+use crate::reporter;
+use crate::shutdown;
+use crate::topology::{self, NeuronSpec, NeuronTask, ReconcilePlan, Topology, TopologyError};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+
+/// A neuron that stays up this long after a restart gets its backoff reset.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Error, Debug)]
+pub enum ProcessError {
+    #[error("Neuron {0} is not managed by this supervisor")]
+    NotManaged(String),
+    #[error("Failed to spawn Neuron {0}: {1}")]
+    Spawn(String, std::io::Error),
+    #[error(transparent)]
+    Topology(#[from] TopologyError),
+}
+
+/// When a managed neuron is brought back after it exits or stops heartbeating.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    Never,
+    /// Restart unless a child process exited successfully.
+    OnFailure,
+    Always,
+}
+
+fn default_policy() -> RestartPolicy {
+    RestartPolicy::OnFailure
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+/// Per-neuron restart behaviour; consecutive restarts back off exponentially
+/// from `initial_backoff_ms` up to `max_backoff_ms`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestartConfig {
+    #[serde(default = "default_policy")]
+    pub policy: RestartPolicy,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            policy: default_policy(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+        }
+    }
+}
+
+impl RestartConfig {
+    fn initial_backoff(&self) -> Duration {
+        Duration::from_millis(self.initial_backoff_ms)
+    }
+
+    fn max_backoff(&self) -> Duration {
+        Duration::from_millis(self.max_backoff_ms)
+    }
+}
+
+/// A running neuron: a tokio task in this process or a child process.
+enum Instance {
+    Task(NeuronTask),
+    Child(Child),
+}
+
+impl Instance {
+    /// `Some(success)` once the instance has exited.
+    fn exited(&mut self) -> Option<bool> {
+        match self {
+            Instance::Task(task) => {
+                // Neuron servers run forever, so a finished task has failed.
+                if task.server.is_finished() {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            Instance::Child(child) => match child.try_wait() {
+                Ok(Some(status)) => Some(status.success()),
+                Ok(None) => None,
+                Err(_) => Some(false),
+            },
+        }
+    }
+
+    /// Stops the instance in the background: an in-process neuron drains,
+    /// flushes and deregisters from the supervisor at `supervisor_endpoint`, a
+    /// child process gets SIGTERM. Whatever is still running after `deadline`
+    /// is aborted or killed. Returns `None` if the instance is a child process
+    /// that already exited.
+    fn stop(self, neuron_id: String, deadline: Duration, supervisor_endpoint: String) -> Option<JoinHandle<()>> {
+        if let Instance::Child(child) = &self {
+            child.id()?;
+        }
+        Some(tokio::spawn(async move {
+            match self {
+                Instance::Task(task) => {
+                    if tokio::time::timeout(deadline, task.neuron.shutdown()).await.is_err() {
+                        log::warn!("Neuron {} did not shut down within {}s", neuron_id, deadline.as_secs());
+                    }
+                    task.server.abort();
+                    if let Err(e) = registry::deregister(supervisor_endpoint, neuron_id.clone()).await {
+                        log::warn!("Failed to deregister Neuron {}: {}", neuron_id, e);
+                    }
+                }
+                Instance::Child(mut child) => {
+                    terminate(&child);
+                    if tokio::time::timeout(deadline, child.wait()).await.is_err() {
+                        log::warn!("Neuron {} did not exit within {}s, killing it", neuron_id, deadline.as_secs());
+                        if let Err(e) = child.kill().await {
+                            log::warn!("Failed to kill neuron process: {}", e);
+                        }
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// Sends SIGTERM to `child` unless it has already been reaped.
+fn terminate(child: &Child) {
+    if let Some(pid) = child.id() {
+        // SAFETY: kill(2) takes no pointers.
+        if unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) } != 0 {
+            log::warn!(
+                "Failed to send SIGTERM to neuron process {}: {}",
+                pid,
+                std::io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+struct Managed {
+    instance: Option<Instance>,
+    // The previous instance while it shuts down.
+    stopping: Option<JoinHandle<()>>,
+    started_at: Instant,
+    backoff: Duration,
+    restarts: u32,
+    restart_at: Option<Instant>,
+}
+
+/// Owns the neurons the supervisor instantiates from its topology: starts
+/// them, restarts them according to their `RestartConfig` and stops them.
+pub struct ProcessManager {
+    topology: Option<Topology>,
+    neurons: HashMap<String, Managed>,
+    // Neurons removed from the topology while they shut down.
+    retiring: HashMap<String, JoinHandle<()>>,
+    stop_deadline: Duration,
+    supervisor_endpoint: String,
+    data_dir: PathBuf,
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self {
+            topology: None,
+            neurons: HashMap::new(),
+            retiring: HashMap::new(),
+            stop_deadline: shutdown::DEFAULT_DEADLINE,
+            supervisor_endpoint: reporter::supervisor_endpoint(),
+            data_dir: PathBuf::from("."),
+        }
+    }
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gives stopped neurons `deadline` to shut down gracefully instead of
+    /// `shutdown::DEFAULT_DEADLINE`.
+    pub fn with_stop_deadline(mut self, deadline: Duration) -> Self {
+        self.stop_deadline = deadline;
+        self
+    }
+
+    /// Has managed neurons report to the supervisor at `endpoint` instead of
+    /// `reporter::supervisor_endpoint()`.
+    pub fn with_supervisor_endpoint(mut self, endpoint: String) -> Self {
+        self.supervisor_endpoint = endpoint;
+        self
+    }
+
+    /// Keeps the databases of managed neurons under `dir` instead of the
+    /// working directory. Child processes run in it.
+    pub fn with_data_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.data_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn topology(&self) -> Option<&Topology> {
+        self.topology.as_ref()
+    }
+
+    pub fn is_managed(&self, neuron_id: &str) -> bool {
+        self.neurons.contains_key(neuron_id)
+    }
+
+    pub fn is_running(&self, neuron_id: &str) -> bool {
+        self.neurons
+            .get(neuron_id)
+            .map_or(false, |managed| managed.instance.is_some())
+    }
+
+    /// Replaces the desired topology, starting and stopping managed neurons to
+    /// match it and restarting those whose spec or connections changed.
+    /// Neurons stopped on command stay stopped and pick up the change when
+    /// restarted. A neuron added back while its old instance still shuts down
+    /// is started by a `supervise` pass once that instance has stopped.
+    pub fn apply(&mut self, topology: Topology) -> Result<ReconcilePlan, ProcessError> {
+        topology.validate()?;
+        let known: HashSet<String> = self.neurons.keys().cloned().collect();
+        let plan = ReconcilePlan::new(&topology, self.topology.as_ref(), &known);
+        self.retiring.retain(|_, stopping| !stopping.is_finished());
+        for id in &plan.stop {
+            if let Some(managed) = self.neurons.remove(id) {
+                log::info!("Stopping Neuron {}: no longer in topology", id);
+                let stopping = match managed.instance {
+                    Some(instance) => instance.stop(id.clone(), self.stop_deadline, self.supervisor_endpoint.clone()),
+                    None => managed.stopping,
+                };
+                if let Some(stopping) = stopping {
+                    self.retiring.insert(id.clone(), stopping);
+                }
+            }
+        }
+        self.topology = Some(topology);
+        for id in &plan.restart {
+            if self.is_running(id) {
+                log::info!("Restarting Neuron {}: its spec changed", id);
+                self.restart(id)?;
+            }
+        }
+        for id in &plan.start {
+            let backoff = self.restart_config(id).initial_backoff();
+            let (instance, stopping, restart_at) = match self.retiring.remove(id) {
+                Some(stopping) => (None, Some(stopping), Some(Instant::now())),
+                None => (Some(self.spawn(id)?), None, None),
+            };
+            self.neurons.insert(
+                id.clone(),
+                Managed {
+                    instance,
+                    stopping,
+                    started_at: Instant::now(),
+                    backoff,
+                    restarts: 0,
+                    restart_at,
+                },
+            );
+        }
+        Ok(plan)
+    }
+
+    /// Stops a managed neuron; it is not restarted until `restart` is called.
+    pub fn stop(&mut self, neuron_id: &str) -> Result<(), ProcessError> {
+        let managed = self
+            .neurons
+            .get_mut(neuron_id)
+            .ok_or_else(|| ProcessError::NotManaged(neuron_id.to_string()))?;
+        log::info!("Stopping Neuron {}", neuron_id);
+        if let Some(instance) = managed.instance.take() {
+            managed.stopping = instance.stop(
+                neuron_id.to_string(),
+                self.stop_deadline,
+                self.supervisor_endpoint.clone(),
+            );
+        }
+        managed.restart_at = None;
+        Ok(())
+    }
+
+    /// Stops every managed neuron, for shutting the supervisor down. Returns
+    /// the ids of those that were running or still shutting down after being
+    /// removed from the topology, each with a handle that completes once the
+    /// neuron has stopped.
+    pub fn stop_all(&mut self) -> Vec<(String, JoinHandle<()>)> {
+        let deadline = self.stop_deadline;
+        let supervisor_endpoint = self.supervisor_endpoint.clone();
+        let mut stopped: Vec<_> = self.retiring.drain().collect();
+        for (id, managed) in self.neurons.iter_mut() {
+            managed.restart_at = None;
+            if let Some(stopping) = managed
+                .instance
+                .take()
+                .and_then(|instance| instance.stop(id.clone(), deadline, supervisor_endpoint.clone()))
+            {
+                stopped.push((id.clone(), stopping));
+            }
+        }
+        stopped.sort_by(|a, b| a.0.cmp(&b.0));
+        stopped
+    }
+
+    /// Stops a managed neuron if it is running and has a `supervise` pass
+    /// start it again once the old instance has shut down and released its
+    /// database, resetting its backoff.
+    pub fn restart(&mut self, neuron_id: &str) -> Result<(), ProcessError> {
+        self.stop(neuron_id)?;
+        let backoff = self.restart_config(neuron_id).initial_backoff();
+        let managed = self.neurons.get_mut(neuron_id).expect("Managed neuron disappeared");
+        managed.backoff = backoff;
+        managed.restarts = 0;
+        managed.restart_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Detects managed neurons that exited or stopped heartbeating and restarts
+    /// those whose policy allows it once their backoff has elapsed. Returns a
+    /// message for every failure and restart.
+    pub fn supervise(&mut self, registry: &NeuronRegistry, now: Instant) -> Vec<(String, String)> {
+        let mut events = Vec::new();
+        let mut due = Vec::new();
+        let topology = match &self.topology {
+            Some(topology) => topology,
+            None => return events,
+        };
+        for (id, managed) in self.neurons.iter_mut() {
+            let config = topology.neuron(id).map(|spec| spec.restart.clone()).unwrap_or_default();
+            if let Some(restart_at) = managed.restart_at {
+                let stopped = managed.stopping.as_ref().map_or(true, JoinHandle::is_finished);
+                if restart_at <= now && stopped {
+                    due.push(id.clone());
+                }
+                continue;
+            }
+            let instance = match managed.instance.as_mut() {
+                Some(instance) => instance,
+                None => continue,
+            };
+
+            // A fresh instance gets one lease to register before its heartbeat counts.
+            let heartbeat_lost = registry.get(id).is_some()
+                && !registry.is_alive(id)
+                && now.saturating_duration_since(managed.started_at) > registry.lease();
+            let failure = match instance.exited() {
+                Some(true) => Some((false, "exited")),
+                Some(false) => Some((true, "failed")),
+                None if heartbeat_lost => Some((true, "stopped sending heartbeats")),
+                None => None,
+            };
+            let (failed, reason) = match failure {
+                Some(failure) => failure,
+                None => {
+                    if managed.restarts > 0 && now.saturating_duration_since(managed.started_at) > STABLE_AFTER {
+                        managed.restarts = 0;
+                        managed.backoff = config.initial_backoff();
+                    }
+                    continue;
+                }
+            };
+
+            let instance = managed.instance.take().expect("Instance checked above");
+            managed.stopping = instance.stop(id.clone(), self.stop_deadline, self.supervisor_endpoint.clone());
+            let restart = match config.policy {
+                RestartPolicy::Always => true,
+                RestartPolicy::OnFailure => failed,
+                RestartPolicy::Never => false,
+            };
+            if restart {
+                managed.restart_at = Some(now + managed.backoff);
+                events.push((
+                    id.clone(),
+                    format!(
+                        "Neuron {} {}; restarting in {:.1}s",
+                        id,
+                        reason,
+                        managed.backoff.as_secs_f64()
+                    ),
+                ));
+                managed.backoff = (managed.backoff * 2).min(config.max_backoff());
+            } else {
+                events.push((id.clone(), format!("Neuron {} {}; not restarting", id, reason)));
+            }
+        }
+
+        for id in due {
+            let result = self.spawn(&id);
+            let config = self.restart_config(&id);
+            let managed = self.neurons.get_mut(&id).expect("Managed neuron disappeared");
+            managed.restart_at = None;
+            match result {
+                Ok(instance) => {
+                    managed.instance = Some(instance);
+                    managed.started_at = now;
+                    managed.restarts += 1;
+                    events.push((id.clone(), format!("Neuron {} restarted (attempt {})", id, managed.restarts)));
+                }
+                Err(e) => {
+                    // Try again after the next backoff.
+                    log::error!("{}", e);
+                    managed.restart_at = Some(now + managed.backoff);
+                    managed.backoff = (managed.backoff * 2).min(config.max_backoff());
+                    events.push((id.clone(), format!("Failed to restart Neuron {}: {}", id, e)));
+                }
+            }
+        }
+        events
+    }
+
+    fn restart_config(&self, neuron_id: &str) -> RestartConfig {
+        self.topology
+            .as_ref()
+            .and_then(|topology| topology.neuron(neuron_id))
+            .map(|spec| spec.restart.clone())
+            .unwrap_or_default()
+    }
+
+    fn spawn(&self, neuron_id: &str) -> Result<Instance, ProcessError> {
+        let topology = self.topology.as_ref().ok_or_else(|| ProcessError::NotManaged(neuron_id.to_string()))?;
+        let spec = topology
+            .neuron(neuron_id)
+            .ok_or_else(|| ProcessError::NotManaged(neuron_id.to_string()))?;
+        log::info!("Starting Neuron {} on {:?}", neuron_id, spec.listen_addr);
+        if spec.command.is_empty() {
+            Ok(Instance::Task(topology::spawn_neuron(
+                topology,
+                spec,
+                &self.supervisor_endpoint,
+                &self.data_dir,
+            )?))
+        } else {
+            spawn_process(topology, spec, &self.supervisor_endpoint, &self.data_dir).map(Instance::Child)
+        }
+    }
+}
+
+/// Runs `spec.command` in `data_dir` as a child process configured through the
+/// same environment variables `main` reads, serving only its neuron.
+fn spawn_process(
+    topology: &Topology,
+    spec: &NeuronSpec,
+    supervisor_endpoint: &str,
+    data_dir: &Path,
+) -> Result<Child, ProcessError> {
+    let synapses = serde_json::to_string(&topology.synapse_config(&spec.id)).expect("Synapse config serializes");
+    Command::new(&spec.command[0])
+        .args(&spec.command[1..])
+        .env("NEURON_ID", &spec.id)
+        .env("NUM_INPUTS", spec.num_inputs.to_string())
+        .env("NEURON_ADDR", spec.listen_addr.as_deref().unwrap_or_default())
+        .env("NEURON_ENDPOINT", &spec.endpoint)
+        .env("SYNAPSES", synapses)
+        .env("SUPERVISOR_ENDPOINT", supervisor_endpoint)
+        .env("NEURON_ONLY", "1")
+        .current_dir(data_dir)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| ProcessError::Spawn(spec.id.clone(), e))
+}
@@ -1,4 +1,7 @@
 // proto/mod.rs
+/// Encoded descriptors of every service in this module, for server reflection.
+pub const FILE_DESCRIPTOR_SET: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/neurox_descriptor.bin"));
+
 pub mod neuron {
     tonic::include_proto!("neuron");
     }
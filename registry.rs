@@ -0,0 +1,207 @@
+// registry.rs
+use crate::proto::supervisor_client::SupervisorClient;
+use crate::proto::{DeregistrationRequest, HeartbeatRequest, NeuronRegistration};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tonic::Request;
+
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(15);
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// What a neuron told the supervisor about itself when it registered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeuronInfo {
+    pub neuron_id: String,
+    pub address: String,
+    pub num_inputs: usize,
+    pub activation: String,
+    pub extensions: Vec<String>,
+}
+
+impl From<NeuronRegistration> for NeuronInfo {
+    fn from(registration: NeuronRegistration) -> Self {
+        Self {
+            neuron_id: registration.neuron_id,
+            address: registration.address,
+            num_inputs: registration.num_inputs as usize,
+            activation: registration.activation,
+            extensions: registration.extensions,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Lease {
+    info: NeuronInfo,
+    last_heartbeat: Instant,
+    expired: bool,
+}
+
+/// The supervisor's view of which neurons exist and which are still alive.
+/// A neuron whose lease runs out without a heartbeat is reported by `expire`
+/// once, and becomes alive again with its next heartbeat or registration.
+#[derive(Debug)]
+pub struct NeuronRegistry {
+    lease: Duration,
+    leases: Mutex<HashMap<String, Lease>>,
+}
+
+impl NeuronRegistry {
+    pub fn new(lease: Duration) -> Self {
+        Self {
+            lease,
+            leases: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn lease(&self) -> Duration {
+        self.lease
+    }
+
+    /// How often neurons should send heartbeats: several per lease, so that a
+    /// single lost heartbeat does not expire it.
+    pub fn heartbeat_interval(&self) -> Duration {
+        self.lease / 3
+    }
+
+    /// Registers or re-registers a neuron, starting a fresh lease.
+    pub fn register(&self, info: NeuronInfo, now: Instant) {
+        let mut leases = self.leases.lock().expect("Registry lock poisoned");
+        leases.insert(
+            info.neuron_id.clone(),
+            Lease {
+                info,
+                last_heartbeat: now,
+                expired: false,
+            },
+        );
+    }
+
+    /// Renews the lease of `neuron_id`. Returns `false` if it never registered.
+    pub fn heartbeat(&self, neuron_id: &str, now: Instant) -> bool {
+        let mut leases = self.leases.lock().expect("Registry lock poisoned");
+        match leases.get_mut(neuron_id) {
+            Some(lease) => {
+                if lease.expired {
+                    log::info!("Neuron {} is reachable again", neuron_id);
+                }
+                lease.last_heartbeat = now;
+                lease.expired = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forgets a neuron that shut down on purpose. Returns `false` if it was
+    /// not registered.
+    pub fn deregister(&self, neuron_id: &str) -> bool {
+        let mut leases = self.leases.lock().expect("Registry lock poisoned");
+        leases.remove(neuron_id).is_some()
+    }
+
+    /// Marks every neuron whose lease ran out by `now` as expired and returns
+    /// the ids of those that were alive until now.
+    pub fn expire(&self, now: Instant) -> Vec<String> {
+        let mut leases = self.leases.lock().expect("Registry lock poisoned");
+        let mut expired = Vec::new();
+        for (neuron_id, lease) in leases.iter_mut() {
+            if !lease.expired && now.saturating_duration_since(lease.last_heartbeat) > self.lease {
+                lease.expired = true;
+                expired.push(neuron_id.clone());
+            }
+        }
+        expired.sort();
+        expired
+    }
+
+    pub fn get(&self, neuron_id: &str) -> Option<NeuronInfo> {
+        let leases = self.leases.lock().expect("Registry lock poisoned");
+        leases.get(neuron_id).map(|lease| lease.info.clone())
+    }
+
+    pub fn is_alive(&self, neuron_id: &str) -> bool {
+        let leases = self.leases.lock().expect("Registry lock poisoned");
+        leases.get(neuron_id).map_or(false, |lease| !lease.expired)
+    }
+
+    pub fn neurons(&self) -> Vec<NeuronInfo> {
+        let leases = self.leases.lock().expect("Registry lock poisoned");
+        let mut neurons: Vec<NeuronInfo> = leases.values().map(|lease| lease.info.clone()).collect();
+        neurons.sort_by(|a, b| a.neuron_id.cmp(&b.neuron_id));
+        neurons
+    }
+}
+
+/// Neuron side of the protocol: registers with the supervisor at
+/// `supervisor_endpoint` and keeps the lease alive, registering again whenever
+/// the supervisor restarts or the connection drops. `registered` tells whether
+/// the supervisor currently holds a lease for the neuron.
+pub async fn maintain_registration(
+    supervisor_endpoint: String,
+    registration: NeuronRegistration,
+    registered: watch::Sender<bool>,
+) {
+    let neuron_id = registration.neuron_id.clone();
+    loop {
+        // Nobody may be watching; the lease is maintained either way.
+        let _ = registered.send(false);
+        let mut client = match SupervisorClient::connect(supervisor_endpoint.clone()).await {
+            Ok(client) => client,
+            Err(e) => {
+                log::warn!("Failed to connect to supervisor for registration: {}", e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let interval = match client.register_neuron(Request::new(registration.clone())).await {
+            Ok(response) => {
+                log::info!("Neuron {} registered with supervisor", neuron_id);
+                let _ = registered.send(true);
+                Duration::from_millis(response.into_inner().heartbeat_interval_ms.max(1))
+            }
+            Err(e) => {
+                log::warn!("Failed to register Neuron {}: {}", neuron_id, e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        loop {
+            tokio::time::sleep(interval).await;
+            let request = Request::new(HeartbeatRequest {
+                neuron_id: neuron_id.clone(),
+            });
+            match client.heartbeat(request).await {
+                Ok(response) if response.get_ref().registered => {}
+                Ok(_) => {
+                    log::info!("Supervisor forgot Neuron {}, registering again", neuron_id);
+                    break;
+                }
+                Err(e) => {
+                    log::warn!("Heartbeat of Neuron {} failed: {}", neuron_id, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Tells the supervisor that the neuron is going away, so that it is not
+/// reported unreachable. Stop `maintain_registration` first, or it registers
+/// the neuron again.
+pub async fn deregister(supervisor_endpoint: String, neuron_id: String) -> Result<(), String> {
+    let mut client = SupervisorClient::connect(supervisor_endpoint)
+        .await
+        .map_err(|e| e.to_string())?;
+    client
+        .deregister_neuron(Request::new(DeregistrationRequest {
+            neuron_id: neuron_id.clone(),
+        }))
+        .await
+        .map_err(|e| e.to_string())?;
+    log::info!("Neuron {} deregistered from supervisor", neuron_id);
+    Ok(())
+}
@@ -0,0 +1,286 @@
+// reporter.rs
+use crate::proto::supervisor_client::SupervisorClient;
+use crate::proto::SupervisorRequest;
+use crate::telemetry;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::Notify;
+use tonic::transport::{Channel, Endpoint};
+
+const DEFAULT_SUPERVISOR_ENDPOINT: &str = "http://[::1]:50052";
+/// Metrics reports kept while the supervisor is unreachable; the oldest are dropped first.
+const METRICS_BUFFER_CAPACITY: usize = 256;
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The supervisor a neuron reports to: `SUPERVISOR_ENDPOINT`, or
+/// `SUPERVISOR_ADDR` when it is a URL rather than a listen address.
+pub fn supervisor_endpoint() -> String {
+    env::var("SUPERVISOR_ENDPOINT")
+        .ok()
+        .or_else(|| env::var("SUPERVISOR_ADDR").ok().filter(|addr| addr.contains("://")))
+        .unwrap_or_else(|| DEFAULT_SUPERVISOR_ENDPOINT.to_string())
+}
+
+#[derive(Debug, Default)]
+struct Pending {
+    // Only the latest status of each neuron is worth sending.
+    status: HashMap<String, String>,
+    metrics: VecDeque<(String, HashMap<String, f64>)>,
+    // Totals since the reporter was created; not taken along with the reports.
+    dropped_metrics: u64,
+    logged_dropped_metrics: u64,
+}
+
+impl Pending {
+    fn push_metrics(&mut self, neuron_id: String, metrics: HashMap<String, f64>) {
+        if self.metrics.len() == METRICS_BUFFER_CAPACITY {
+            self.metrics.pop_front();
+            self.dropped_metrics += 1;
+        }
+        self.metrics.push_back((neuron_id, metrics));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.status.is_empty() && self.metrics.is_empty()
+    }
+}
+
+struct Shared {
+    endpoint: String,
+    pending: Mutex<Pending>,
+    notify: Notify,
+    // Set while the task holds reports taken from `pending`.
+    sending: AtomicBool,
+    flushed: Notify,
+    started: AtomicBool,
+}
+
+impl Shared {
+    fn take(&self) -> Pending {
+        let mut pending = self.pending.lock().expect("Reporter buffer lock poisoned");
+        if pending.dropped_metrics > pending.logged_dropped_metrics {
+            log::warn!(
+                "Dropped {} metrics reports while the supervisor was unreachable",
+                pending.dropped_metrics - pending.logged_dropped_metrics
+            );
+            pending.logged_dropped_metrics = pending.dropped_metrics;
+        }
+        Pending {
+            status: std::mem::take(&mut pending.status),
+            metrics: std::mem::take(&mut pending.metrics),
+            ..Pending::default()
+        }
+    }
+
+    /// Puts back reports that could not be delivered, behind anything newer.
+    fn requeue(&self, unsent: Pending) {
+        let mut pending = self.pending.lock().expect("Reporter buffer lock poisoned");
+        for (neuron_id, status) in unsent.status {
+            pending.status.entry(neuron_id).or_insert(status);
+        }
+        let newer = std::mem::take(&mut pending.metrics);
+        for (neuron_id, metrics) in unsent.metrics.into_iter().chain(newer) {
+            pending.push_metrics(neuron_id, metrics);
+        }
+    }
+}
+
+/// Delivers neuron status and metrics to the supervisor from a background task.
+/// Reporting never blocks or fails the caller: reports are buffered while the
+/// supervisor is unreachable and sent once it is back. Clones share one
+/// connection and one buffer, so a `Layer` reports through a single client.
+#[derive(Clone)]
+pub struct Reporter {
+    shared: Arc<Shared>,
+}
+
+impl Reporter {
+    pub fn new(endpoint: String) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                endpoint,
+                pending: Mutex::new(Pending::default()),
+                notify: Notify::new(),
+                sending: AtomicBool::new(false),
+                flushed: Notify::new(),
+                started: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(supervisor_endpoint())
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.shared.endpoint
+    }
+
+    /// Replaces any status of `neuron_id` that has not been sent yet.
+    pub fn report_status(&self, neuron_id: &str, status: String) {
+        self.shared
+            .pending
+            .lock()
+            .expect("Reporter buffer lock poisoned")
+            .status
+            .insert(neuron_id.to_string(), status);
+        self.wake();
+    }
+
+    pub fn report_metrics(&self, neuron_id: &str, metrics: HashMap<String, f64>) {
+        self.shared
+            .pending
+            .lock()
+            .expect("Reporter buffer lock poisoned")
+            .push_metrics(neuron_id.to_string(), metrics);
+        self.wake();
+    }
+
+    /// Number of status updates and metrics reports waiting to be sent.
+    pub fn pending(&self) -> (usize, usize) {
+        let pending = self.shared.pending.lock().expect("Reporter buffer lock poisoned");
+        (pending.status.len(), pending.metrics.len())
+    }
+
+    /// Number of metrics reports dropped so far because the buffer was full.
+    pub fn dropped_metrics(&self) -> u64 {
+        self.shared
+            .pending
+            .lock()
+            .expect("Reporter buffer lock poisoned")
+            .dropped_metrics
+    }
+
+    /// Waits until everything reported so far has reached the supervisor.
+    /// Never returns while it is unreachable, so callers bound it with a
+    /// deadline.
+    pub async fn flush(&self) {
+        loop {
+            let flushed = self.shared.flushed.notified();
+            if !self.shared.sending.load(Ordering::Acquire) && self.pending() == (0, 0) {
+                return;
+            }
+            self.wake();
+            flushed.await;
+        }
+    }
+
+    fn wake(&self) {
+        // The task is started on first use so that neurons can be built
+        // outside of a runtime.
+        if !self.shared.started.load(Ordering::Acquire) {
+            if let Ok(handle) = Handle::try_current() {
+                if !self.shared.started.swap(true, Ordering::AcqRel) {
+                    handle.spawn(run(self.shared.clone()));
+                }
+            }
+        }
+        self.shared.notify.notify_one();
+    }
+}
+
+async fn run(shared: Arc<Shared>) {
+    let mut client = None;
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        shared.notify.notified().await;
+        shared.sending.store(true, Ordering::Release);
+        loop {
+            let pending = shared.take();
+            if pending.is_empty() {
+                break;
+            }
+            if client.is_none() {
+                match connect(&shared.endpoint).await {
+                    Ok(connected) => client = Some(connected),
+                    Err(e) => {
+                        log::warn!("Failed to connect to supervisor at {}: {}", shared.endpoint, e);
+                        shared.requeue(pending);
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                        continue;
+                    }
+                }
+            }
+            let connected = client.as_mut().expect("Supervisor client connected");
+            match send(connected, pending).await {
+                Ok(()) => backoff = INITIAL_BACKOFF,
+                Err((e, unsent)) => {
+                    log::warn!("Failed to report to supervisor at {}: {}", shared.endpoint, e);
+                    shared.requeue(unsent);
+                    client = None;
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+        shared.sending.store(false, Ordering::Release);
+        shared.flushed.notify_waiters();
+    }
+}
+
+async fn connect(endpoint: &str) -> Result<SupervisorClient<Channel>, String> {
+    let channel = Endpoint::from_shared(endpoint.to_string())
+        .map_err(|e| e.to_string())?
+        .timeout(REQUEST_TIMEOUT)
+        .connect()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(SupervisorClient::new(channel))
+}
+
+/// Sends everything in `pending`, returning what was left unsent on failure.
+async fn send(client: &mut SupervisorClient<Channel>, pending: Pending) -> Result<(), (String, Pending)> {
+    let mut status: Vec<_> = pending.status.into_iter().collect();
+    let mut metrics = pending.metrics;
+    while let Some((neuron_id, neuron_status)) = status.pop() {
+        let request = telemetry::traced_request(SupervisorRequest {
+            neuron_id: neuron_id.clone(),
+            status: neuron_status.clone(),
+            metrics: "".to_string(),
+            command: "".to_string(),
+            args: vec![],
+        });
+        if let Err(e) = client.report_neuron_status(request).await {
+            status.push((neuron_id, neuron_status));
+            let unsent = Pending {
+                status: status.into_iter().collect(),
+                metrics,
+                ..Pending::default()
+            };
+            return Err((e.to_string(), unsent));
+        }
+    }
+    while let Some((neuron_id, neuron_metrics)) = metrics.pop_front() {
+        let metrics_json = match serde_json::to_string(&neuron_metrics) {
+            Ok(json) => json,
+            Err(e) => {
+                log::error!("Failed to serialize metrics of Neuron {}: {}", neuron_id, e);
+                continue;
+            }
+        };
+        let request = telemetry::traced_request(SupervisorRequest {
+            neuron_id: neuron_id.clone(),
+            status: "".to_string(),
+            metrics: metrics_json,
+            command: "".to_string(),
+            args: vec![],
+        });
+        if let Err(e) = client.report_neuron_metrics(request).await {
+            metrics.push_front((neuron_id, neuron_metrics));
+            let unsent = Pending {
+                status: HashMap::new(),
+                metrics,
+                ..Pending::default()
+            };
+            return Err((e.to_string(), unsent));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+// shutdown.rs
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal::unix::{signal as unix_signal, SignalKind};
+use tokio::sync::{watch, Notify};
+
+/// How long a process may take to shut down gracefully before it exits anyway.
+pub const DEFAULT_DEADLINE: Duration = Duration::from_secs(30);
+
+/// Completes on the first SIGINT or SIGTERM.
+pub async fn signal() {
+    let mut terminate = match unix_signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            log::error!("Failed to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => log::info!("Received SIGINT"),
+        _ = terminate.recv() => log::info!("Received SIGTERM"),
+    }
+}
+
+/// Completes once `true` is sent on `shutdown`, or its sender is dropped.
+/// Meant for `serve_with_shutdown`.
+pub async fn wait(mut shutdown: watch::Receiver<bool>) {
+    while !*shutdown.borrow() {
+        if shutdown.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Admits work until closed, then lets the owner wait for admitted work to
+/// finish.
+#[derive(Debug, Default)]
+pub struct Drain {
+    in_flight: AtomicUsize,
+    closed: AtomicBool,
+    idle: Notify,
+}
+
+impl Drain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admits one unit of work, which lasts until the guard is dropped.
+    /// Returns `None` once the drain is closed.
+    pub fn enter(drain: &Arc<Drain>) -> Option<DrainGuard> {
+        drain.in_flight.fetch_add(1, Ordering::AcqRel);
+        let guard = DrainGuard { drain: drain.clone() };
+        if drain.closed.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Stops admitting work.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Waits until no admitted work is left. Does not close the drain.
+    pub async fn drained(&self) {
+        loop {
+            let idle = self.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+pub struct DrainGuard {
+    drain: Arc<Drain>,
+}
+
+impl Drop for DrainGuard {
+    fn drop(&mut self) {
+        if self.drain.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.drain.idle.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// spiking.rs
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+const SPIKE_CHANNEL_CAPACITY: usize = 256;
+
+/// Leaky integrate-and-fire parameters. Times are in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifConfig {
+    #[serde(default = "default_tau_ms")]
+    pub tau_ms: f32,
+    #[serde(default = "default_resting_potential")]
+    pub resting_potential: f32,
+    #[serde(default = "default_threshold")]
+    pub threshold: f32,
+    #[serde(default = "default_reset_potential")]
+    pub reset_potential: f32,
+    #[serde(default = "default_refractory_ms")]
+    pub refractory_ms: u64,
+}
+
+fn default_tau_ms() -> f32 {
+    20.0
+}
+
+fn default_resting_potential() -> f32 {
+    0.0
+}
+
+fn default_threshold() -> f32 {
+    1.0
+}
+
+fn default_reset_potential() -> f32 {
+    0.0
+}
+
+fn default_refractory_ms() -> u64 {
+    2
+}
+
+impl Default for LifConfig {
+    fn default() -> Self {
+        Self {
+            tau_ms: default_tau_ms(),
+            resting_potential: default_resting_potential(),
+            threshold: default_threshold(),
+            reset_potential: default_reset_potential(),
+            refractory_ms: default_refractory_ms(),
+        }
+    }
+}
+
+/// Membrane state persisted in `NeuronDb` between inputs and restarts.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MembraneState {
+    pub potential: f32,
+    pub last_update_ms: u64,
+    pub refractory_until_ms: u64,
+    pub last_spike_ms: Option<u64>,
+}
+
+impl MembraneState {
+    pub fn resting(config: &LifConfig) -> Self {
+        Self {
+            potential: config.resting_potential,
+            ..Self::default()
+        }
+    }
+
+    /// Decays the potential towards rest since the last update, adds `current`
+    /// unless refractory, and returns whether the neuron fired at `now_ms`.
+    pub fn integrate(&mut self, config: &LifConfig, current: f32, now_ms: u64) -> bool {
+        let dt = now_ms.saturating_sub(self.last_update_ms) as f32;
+        let decay = (-dt / config.tau_ms).exp();
+        self.potential = config.resting_potential + (self.potential - config.resting_potential) * decay;
+        self.last_update_ms = self.last_update_ms.max(now_ms);
+
+        if now_ms < self.refractory_until_ms {
+            return false;
+        }
+        self.potential += current;
+        if self.potential >= config.threshold {
+            self.potential = config.reset_potential;
+            self.refractory_until_ms = now_ms + config.refractory_ms;
+            self.last_spike_ms = Some(now_ms);
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spike {
+    pub timestamp_ms: u64,
+    pub weight_version: u64,
+}
+
+/// Spiking mode of a neuron: its LIF parameters, membrane and spike fan-out.
+#[derive(Debug)]
+pub struct Spiking {
+    config: LifConfig,
+    membrane: Mutex<MembraneState>,
+    spikes: broadcast::Sender<Spike>,
+}
+
+impl Spiking {
+    pub fn new(config: LifConfig, membrane: MembraneState) -> Self {
+        let (spikes, _) = broadcast::channel(SPIKE_CHANNEL_CAPACITY);
+        Self {
+            config,
+            membrane: Mutex::new(membrane),
+            spikes,
+        }
+    }
+
+    pub fn config(&self) -> &LifConfig {
+        &self.config
+    }
+
+    /// Integrates `current` at `now_ms` against the threshold scaled by
+    /// `threshold_scale` and hands the new membrane state to `persist` while
+    /// still holding the membrane lock, so that concurrent inputs are persisted
+    /// in the order they were integrated. Broadcasts a spike once persisted if
+    /// the neuron fires. Returns the new membrane state and whether it fired.
+    pub fn integrate<E>(
+        &self,
+        current: f32,
+        now_ms: u64,
+        weight_version: u64,
+        threshold_scale: f32,
+        persist: impl FnOnce(&MembraneState) -> Result<(), E>,
+    ) -> Result<(MembraneState, bool), E> {
+        let config = LifConfig {
+            threshold: self.config.threshold * threshold_scale,
+            ..self.config.clone()
+        };
+        let mut membrane = self.membrane.lock().expect("Membrane lock poisoned");
+        let spiked = membrane.integrate(&config, current, now_ms);
+        persist(&membrane)?;
+        if spiked {
+            // No subscribers is not an error; the spike is simply unobserved.
+            let _ = self.spikes.send(Spike {
+                timestamp_ms: now_ms,
+                weight_version,
+            });
+        }
+        Ok((membrane.clone(), spiked))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Spike> {
+        self.spikes.subscribe()
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
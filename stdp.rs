@@ -0,0 +1,143 @@
+// stdp.rs
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Pair-based spike-timing-dependent plasticity parameters. Times are in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StdpConfig {
+    /// Decay constant of the pre-synaptic trace (potentiation window).
+    #[serde(default = "default_tau_ms")]
+    pub tau_plus_ms: f32,
+    /// Decay constant of the post-synaptic trace (depression window).
+    #[serde(default = "default_tau_ms")]
+    pub tau_minus_ms: f32,
+    #[serde(default = "default_a_plus")]
+    pub a_plus: f32,
+    #[serde(default = "default_a_minus")]
+    pub a_minus: f32,
+    /// Weight bounds STDP does not push a weight beyond. Weights already
+    /// outside them (negative initial weights, say) are left where they are
+    /// until STDP moves them back towards the range.
+    #[serde(default = "default_w_min")]
+    pub w_min: f32,
+    #[serde(default = "default_w_max")]
+    pub w_max: f32,
+}
+
+fn default_tau_ms() -> f32 {
+    20.0
+}
+
+fn default_a_plus() -> f32 {
+    0.01
+}
+
+fn default_a_minus() -> f32 {
+    0.012
+}
+
+fn default_w_min() -> f32 {
+    0.0
+}
+
+fn default_w_max() -> f32 {
+    1.0
+}
+
+impl Default for StdpConfig {
+    fn default() -> Self {
+        Self {
+            tau_plus_ms: default_tau_ms(),
+            tau_minus_ms: default_tau_ms(),
+            a_plus: default_a_plus(),
+            a_minus: default_a_minus(),
+            w_min: default_w_min(),
+            w_max: default_w_max(),
+        }
+    }
+}
+
+/// Per-synapse pre-synaptic traces and the neuron's post-synaptic trace,
+/// persisted in `NeuronDb` next to the weights.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StdpTraces {
+    pub pre: Vec<f32>,
+    pub post: f32,
+    pub last_update_ms: u64,
+}
+
+impl StdpTraces {
+    /// Advances the traces to `now_ms` for one input event and returns the
+    /// weight deltas, scaled by `learning_rate_scale` and then clipped so that
+    /// no weight is pushed beyond `w_min` or `w_max`.
+    pub fn update(
+        &mut self,
+        config: &StdpConfig,
+        inputs: &[f32],
+        spiked: bool,
+        now_ms: u64,
+        weights: &[f32],
+        learning_rate_scale: f32,
+    ) -> Vec<f32> {
+        if self.pre.len() != inputs.len() {
+            self.pre.resize(inputs.len(), 0.0);
+        }
+        let dt = now_ms.saturating_sub(self.last_update_ms) as f32;
+        let pre_decay = (-dt / config.tau_plus_ms).exp();
+        let post_decay = (-dt / config.tau_minus_ms).exp();
+        self.post *= post_decay;
+        self.last_update_ms = self.last_update_ms.max(now_ms);
+
+        let mut deltas = vec![0.0; inputs.len()];
+        for (i, &input) in inputs.iter().enumerate() {
+            self.pre[i] *= pre_decay;
+            if input != 0.0 {
+                // Pre after post: depress by the post-synaptic trace.
+                deltas[i] -= config.a_minus * self.post;
+                self.pre[i] += 1.0;
+            }
+        }
+        if spiked {
+            // Post after pre: potentiate by each pre-synaptic trace.
+            for (delta, pre) in deltas.iter_mut().zip(&self.pre) {
+                *delta += config.a_plus * pre;
+            }
+            self.post += 1.0;
+        }
+
+        for (delta, &weight) in deltas.iter_mut().zip(weights) {
+            *delta = (*delta * learning_rate_scale)
+                .min((config.w_max - weight).max(0.0))
+                .max((config.w_min - weight).min(0.0));
+        }
+        deltas
+    }
+}
+
+#[derive(Debug)]
+pub struct Stdp {
+    config: StdpConfig,
+    traces: Mutex<StdpTraces>,
+}
+
+impl Stdp {
+    pub fn new(config: StdpConfig, traces: StdpTraces) -> Self {
+        Self {
+            config,
+            traces: Mutex::new(traces),
+        }
+    }
+
+    pub fn update(
+        &self,
+        inputs: &[f32],
+        spiked: bool,
+        now_ms: u64,
+        weights: &[f32],
+        learning_rate_scale: f32,
+    ) -> (Vec<f32>, StdpTraces) {
+        let mut traces = self.traces.lock().expect("STDP trace lock poisoned");
+        let deltas = traces.update(&self.config, inputs, spiked, now_ms, weights, learning_rate_scale);
+        (deltas, traces.clone())
+    }
+}
@@ -2,75 +2,279 @@
 use crate::proto::supervisor_server::{Supervisor as SupervisorTrait, SupervisorServer};
 use crate::proto::{
 SupervisorRequest, SupervisorResponse, SupervisorStatusRequest, SupervisorStatusResponse,
-SupervisorMetricsRequest, SupervisorMetricsResponse,
+SupervisorMetricsRequest, SupervisorMetricsResponse, NeuromodulatorLevels, NeuromodulatorSubscription,
+NeuromodulatorUpdate, NeuronRegistration, RegistrationResponse, HeartbeatRequest, HeartbeatResponse,
+MetricsQuery, MetricsQueryResponse, MetricSeries, MetricBucket, DeregistrationRequest, ParametersRequest,
 };
+use crate::proto::neuron_service_client::NeuronServiceClient;
+use crate::alerting::{AlertEngine, AlertRule};
+use crate::health;
+use crate::metrics_exporter;
+use crate::metrics_history::MetricsHistory;
+use crate::spiking;
+use crate::registry::{self, NeuronRegistry};
+use crate::shutdown;
+use crate::process_manager::{ProcessError, ProcessManager};
+use crate::supervisor_state::SupervisorState;
+use crate::telemetry;
+use crate::topology::{ReconcilePlan, Topology};
 use std::collections::HashMap;
-use tokio::sync::mpsc;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
 use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{transport::Server, Request, Response, Status};
+use tonic_health::server::HealthReporter;
+
+const LEASE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const SUPERVISE_INTERVAL: Duration = Duration::from_secs(1);
+const ALERT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 
 pub struct Supervisor {
-neuron_status_sender: mpsc::Sender<(String, String)>,
 telegram_bot_sender: mpsc::Sender<(String, String)>,
-neuron_status: HashMap<String, String>,
-neuron_metrics: HashMap<String, HashMap<String, f64>>,
+state: Arc<SupervisorState>,
+process_manager: Arc<Mutex<ProcessManager>>,
+registry: Arc<NeuronRegistry>,
+alerts: Arc<Mutex<AlertEngine>>,
 }
 
 impl Supervisor {
-pub fn new(
-neuron_status_sender: mpsc::Sender<(String, String)>,
-telegram_bot_sender: mpsc::Sender<(String, String)>,
-) -> Self {
+pub fn new(telegram_bot_sender: mpsc::Sender<(String, String)>) -> Self {
 Self {
-neuron_status_sender,
 telegram_bot_sender,
-neuron_status: HashMap::new(),
-neuron_metrics: HashMap::new(),
+state: Arc::new(SupervisorState::new()),
+process_manager: Arc::new(Mutex::new(ProcessManager::new())),
+registry: Arc::new(NeuronRegistry::new(registry::DEFAULT_LEASE)),
+alerts: Arc::new(Mutex::new(AlertEngine::new(Vec::new()))),
+}
+}
+
+/// Keeps metrics history according to `history`'s retention instead of the default.
+/// Must be called before the supervisor starts serving.
+pub fn with_metrics_history(mut self, history: MetricsHistory) -> Self {
+    self.state = Arc::new(SupervisorState::with_history(history));
+    self
+}
+
+/// Expires neurons that send no heartbeat for `lease` instead of
+/// `registry::DEFAULT_LEASE`. Must be called before the supervisor starts serving.
+pub fn with_lease(mut self, lease: Duration) -> Self {
+    self.registry = Arc::new(NeuronRegistry::new(lease));
+    self
+}
+
+/// Gives managed neurons `deadline` to stop gracefully before they are killed.
+/// Must be called before a topology is applied.
+pub fn with_stop_deadline(mut self, deadline: Duration) -> Self {
+    self.process_manager = Arc::new(Mutex::new(ProcessManager::new().with_stop_deadline(deadline)));
+    self
+}
+
+/// Evaluates `rules` against neuron metrics and status once `watch_alerts` runs.
+pub fn with_alert_rules(mut self, rules: Vec<AlertRule>) -> Self {
+    self.alerts = Arc::new(Mutex::new(AlertEngine::new(rules)));
+    self
+}
+
+/// Replaces the desired network topology and reconciles the neurons this
+/// supervisor instantiates to match it.
+pub fn apply_topology(&mut self, topology: Topology) -> Result<ReconcilePlan, ProcessError> {
+    self.process_manager
+        .lock()
+        .expect("Process manager lock poisoned")
+        .apply(topology)
+}
+
+/// Serves every neuron's latest metrics and status for Prometheus on `addr`.
+pub fn serve_metrics(&self, addr: SocketAddr) -> JoinHandle<()> {
+    let state = self.state.clone();
+    metrics_exporter::spawn_exporter(addr, move || state.snapshot())
+}
+
+/// Periodically restarts managed neurons that exited or lost their heartbeat
+/// lease, as their restart policies allow, and tells the Telegram bot.
+pub fn watch_processes(&self) -> JoinHandle<()> {
+    let process_manager = self.process_manager.clone();
+    let registry = self.registry.clone();
+    let telegram_bot_sender = self.telegram_bot_sender.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SUPERVISE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let events = process_manager
+                .lock()
+                .expect("Process manager lock poisoned")
+                .supervise(&registry, Instant::now());
+            for (neuron_id, message) in events {
+                log::warn!("{}", message);
+                if telegram_bot_sender
+                    .send(("/restart".to_string(), message))
+                    .await
+                    .is_err()
+                {
+                    log::error!("Telegram bot channel closed while reporting Neuron {}", neuron_id);
+                }
+            }
+        }
+    })
+}
+
+/// Periodically expires the leases of neurons that stopped sending
+/// heartbeats, marking them `Unreachable` and notifying the Telegram bot.
+pub fn watch_leases(&self) -> JoinHandle<()> {
+    let registry = self.registry.clone();
+    let state = self.state.clone();
+    let telegram_bot_sender = self.telegram_bot_sender.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(LEASE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            for neuron_id in registry.expire(Instant::now()) {
+                log::warn!("Neuron {} missed its heartbeat lease", neuron_id);
+                let message = format!(
+                    "Neuron {} is unreachable: no heartbeat for {}s",
+                    neuron_id,
+                    registry.lease().as_secs()
+                );
+                state.clear_metrics(&neuron_id);
+                state.record_status(neuron_id, "Unreachable".to_string());
+                if telegram_bot_sender
+                    .send(("/heartbeat".to_string(), message))
+                    .await
+                    .is_err()
+                {
+                    log::error!("Telegram bot channel closed");
+                }
+            }
+        }
+    })
+}
+
+/// Periodically evaluates the alert rules and pushes firing and resolved
+/// notifications to the Telegram bot.
+pub fn watch_alerts(&self) -> JoinHandle<()> {
+    let alerts = self.alerts.clone();
+    let state = self.state.clone();
+    let telegram_bot_sender = self.telegram_bot_sender.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ALERT_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            let notifications = alerts
+                .lock()
+                .expect("Alert engine lock poisoned")
+                .evaluate(&state.snapshot(), Instant::now());
+            for notification in notifications {
+                log::warn!("{}", notification.message);
+                if telegram_bot_sender
+                    .send(("/alert".to_string(), notification.message))
+                    .await
+                    .is_err()
+                {
+                    log::error!("Telegram bot channel closed");
+                }
+            }
+        }
+    })
+}
+
+/// Reports the supervisor as serving through `reporter` while the Telegram
+/// bot is still receiving its messages.
+pub fn watch_health(&self, reporter: HealthReporter) -> JoinHandle<()> {
+    let telegram_bot_sender = self.telegram_bot_sender.clone();
+    tokio::spawn(health::report_readiness::<SupervisorServer<Supervisor>, _>(reporter, move || {
+        !telegram_bot_sender.is_closed()
+    }))
 }
+
+/// Waits for `shutdown`, then stops every managed neuron and tells the
+/// Telegram bot. The returned task completes once the neurons have stopped,
+/// so keep serving until then for them to report and deregister.
+pub fn watch_shutdown(&self, shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+    let process_manager = self.process_manager.clone();
+    let telegram_bot_sender = self.telegram_bot_sender.clone();
+    tokio::spawn(async move {
+        shutdown::wait(shutdown).await;
+        let stopping = process_manager
+            .lock()
+            .expect("Process manager lock poisoned")
+            .stop_all();
+        let mut stopped = Vec::new();
+        for (neuron_id, stop) in stopping {
+            if let Err(e) = stop.await {
+                log::error!("Failed to stop Neuron {}: {}", neuron_id, e);
+            }
+            stopped.push(neuron_id);
+        }
+        let message = if stopped.is_empty() {
+            "Supervisor shutting down".to_string()
+        } else {
+            format!("Supervisor shutting down; stopped Neurons {}", stopped.join(", "))
+        };
+        log::info!("{}", message);
+        if telegram_bot_sender
+            .send(("/shutdown".to_string(), message))
+            .await
+            .is_err()
+        {
+            log::error!("Telegram bot channel closed");
+        }
+    })
 }
 
-async fn process_neuron_status(&mut self, neuron_id: String, status: String) {
+fn process_neuron_status(&self, neuron_id: String, status: String) {
     log::info!("Received status from Neuron {}: {}", neuron_id, status);
-    self.neuron_status.insert(neuron_id, status);
+    self.state.record_status(neuron_id, status);
 }
 
-async fn process_neuron_metrics(&mut self, neuron_id: String, metrics: HashMap<String, f64>) {
-    log::info!("Received metrics from Neuron {}: {:?}", neuron_id, metrics);
-    self.neuron_metrics.insert(neuron_id, metrics);
+fn process_neuron_metrics(&self, neuron_id: String, metrics: HashMap<String, f64>) {
+    log::debug!("Received metrics from Neuron {}: {:?}", neuron_id, metrics);
+    self.state.record_metrics(neuron_id, metrics);
 }
 
-async fn process_telegram_command(&mut self, command: String, args: Vec<String>) {
+async fn handle_telegram_command(&self, command: String, args: Vec<String>) {
     log::info!("Received Telegram command: {}", command);
     let response = match command.as_str() {
         "/neuron_status" => self.handle_neuron_status().await,
         "/neuron_metrics" => self.handle_neuron_metrics(args).await,
+        "/modulate" => self.handle_modulate(args).await,
+        "/topology" => self.handle_topology().await,
+        "/neurons" => self.handle_neurons().await,
+        "/weights" => self.handle_weights(args).await,
+        "/restart" => self.handle_restart(args).await,
+        "/stop" => self.handle_stop(args).await,
+        "/alerts" => self.handle_alerts().await,
+        "/silence" => self.handle_silence(args).await,
         "/help" => self.handle_help().await,
         _ => "Unknown command. Type /help for available commands.".to_string(),
     };
-    self.telegram_bot_sender
-        .send((command, response))
-        .await
-        .expect("Failed to send response to Telegram bot");
+    if self.telegram_bot_sender.send((command, response)).await.is_err() {
+        log::error!("Telegram bot channel closed");
+    }
 }
 
-async fn handle_neuron_status(&mut self) -> String {
-    if self.neuron_status.is_empty() {
+async fn handle_neuron_status(&self) -> String {
+    let neuron_status = self.state.neuron_status();
+    if neuron_status.is_empty() {
         "No neuron status available.".to_string()
     } else {
         let mut status_messages = Vec::new();
-        for (neuron_id, status) in &self.neuron_status {
+        for (neuron_id, status) in &neuron_status {
             status_messages.push(format!("Neuron {}: {}", neuron_id, status));
         }
         status_messages.join("\n")
     }
 }
 
-async fn handle_neuron_metrics(&mut self, args: Vec<String>) -> String {
+async fn handle_neuron_metrics(&self, args: Vec<String>) -> String {
     if args.is_empty() {
         "Please provide a neuron ID. Usage: /neuron_metrics <neuron_id>".to_string()
     } else {
         let neuron_id = &args[0];
-        if let Some(metrics) = self.neuron_metrics.get(neuron_id) {
+        if let Some(metrics) = self.state.neuron_metrics(neuron_id) {
             let mut metric_messages = Vec::new();
             for (metric_name, metric_value) in metrics {
                 metric_messages.push(format!("{}: {}", metric_name, metric_value));
@@ -82,10 +286,183 @@ async fn handle_neuron_metrics(&mut self, args: Vec<String>) -> String {
     }
 }
 
-async fn handle_help(&mut self) -> String {
+async fn handle_modulate(&self, args: Vec<String>) -> String {
+    if args.is_empty() {
+        let levels = self.state.neuromodulator_levels();
+        if levels.is_empty() {
+            return "No neuromodulators set.".to_string();
+        }
+        let mut level_messages = Vec::new();
+        for (name, level) in levels.iter() {
+            level_messages.push(format!("{}: {}", name, level));
+        }
+        return level_messages.join("\n");
+    }
+    if args.len() != 2 {
+        return "Usage: /modulate <name> <level>".to_string();
+    }
+    match args[1].parse::<f32>() {
+        Ok(level) => {
+            self.state.set_neuromodulator_level(args[0].clone(), level);
+            format!("Neuromodulator {} set to {}", args[0], level)
+        }
+        Err(_) => format!("Invalid level: {}", args[1]),
+    }
+}
+
+async fn handle_topology(&self) -> String {
+    let process_manager = self.process_manager.lock().expect("Process manager lock poisoned");
+    match process_manager.topology() {
+        None => "No topology loaded.".to_string(),
+        Some(topology) => {
+            let mut lines = Vec::new();
+            for spec in &topology.neurons {
+                let managed = if process_manager.is_running(&spec.id) {
+                    " (managed)"
+                } else if process_manager.is_managed(&spec.id) {
+                    " (managed, stopped)"
+                } else {
+                    ""
+                };
+                lines.push(format!("Neuron {}: {} inputs, {}{}", spec.id, spec.num_inputs, spec.activation, managed));
+            }
+            for connection in &topology.connections {
+                lines.push(format!("{} -> {}[{}]", connection.from, connection.to, connection.slot));
+            }
+            lines.join("\n")
+        }
+    }
+}
+
+async fn handle_restart(&self, args: Vec<String>) -> String {
+    if args.len() != 1 {
+        return "Usage: /restart <neuron_id>".to_string();
+    }
+    let result = self
+        .process_manager
+        .lock()
+        .expect("Process manager lock poisoned")
+        .restart(&args[0]);
+    match result {
+        Ok(()) => format!("Restarting Neuron {}", args[0]),
+        Err(e) => e.to_string(),
+    }
+}
+
+async fn handle_stop(&self, args: Vec<String>) -> String {
+    if args.len() != 1 {
+        return "Usage: /stop <neuron_id>".to_string();
+    }
+    let result = self
+        .process_manager
+        .lock()
+        .expect("Process manager lock poisoned")
+        .stop(&args[0]);
+    match result {
+        Ok(()) => format!("Stopped Neuron {}; /restart {} starts it again", args[0], args[0]),
+        Err(e) => e.to_string(),
+    }
+}
+
+async fn handle_alerts(&self) -> String {
+    let alerts = self.alerts.lock().expect("Alert engine lock poisoned");
+    let firing = alerts.firing();
+    if firing.is_empty() {
+        return "No alerts firing.".to_string();
+    }
+    let mut lines = Vec::new();
+    for (rule, neuron_id, reason) in firing {
+        lines.push(format!("{}: Neuron {} {}", rule, neuron_id, reason));
+    }
+    lines.join("\n")
+}
+
+async fn handle_silence(&self, args: Vec<String>) -> String {
+    let now = Instant::now();
+    let mut alerts = self.alerts.lock().expect("Alert engine lock poisoned");
+    if args.is_empty() {
+        let silences = alerts.silences(now);
+        if silences.is_empty() {
+            return "No silences active.".to_string();
+        }
+        let mut lines = Vec::new();
+        for (target, remaining) in silences {
+            lines.push(format!("{}: {} more minutes", target, (remaining.as_secs() + 59) / 60));
+        }
+        return lines.join("\n");
+    }
+    if args.len() != 2 {
+        return "Usage: /silence <rule|neuron_id> <minutes>".to_string();
+    }
+    let minutes = match args[1].parse::<u64>() {
+        Ok(minutes) => minutes,
+        Err(_) => return format!("Invalid minutes: {}", args[1]),
+    };
+    let duration = match minutes.checked_mul(60) {
+        Some(secs) => Duration::from_secs(secs),
+        None => return format!("Invalid minutes: {}", args[1]),
+    };
+    match alerts.silence(args[0].clone(), duration, now) {
+        Ok(()) if minutes == 0 => format!("Unsilenced {}", args[0]),
+        Ok(()) => format!("Silenced {} for {} minutes", args[0], minutes),
+        Err(e) => e.to_string(),
+    }
+}
+
+async fn handle_neurons(&self) -> String {
+    let neurons = self.registry.neurons();
+    if neurons.is_empty() {
+        return "No Neurons registered.".to_string();
+    }
+    let mut lines = Vec::new();
+    for info in neurons {
+        let state = if self.registry.is_alive(&info.neuron_id) { "alive" } else { "unreachable" };
+        lines.push(format!(
+            "Neuron {} at {}: {} inputs, {}, {}",
+            info.neuron_id, info.address, info.num_inputs, info.activation, state
+        ));
+    }
+    lines.join("\n")
+}
+
+async fn handle_weights(&self, args: Vec<String>) -> String {
+    if args.len() != 1 {
+        return "Usage: /weights <neuron_id>".to_string();
+    }
+    let info = match self.registry.get(&args[0]) {
+        Some(info) => info,
+        None => return format!("Neuron {} is not registered.", args[0]),
+    };
+    let mut client = match NeuronServiceClient::connect(info.address.clone()).await {
+        Ok(client) => client,
+        Err(e) => return format!("Failed to reach Neuron {} at {}: {}", args[0], info.address, e),
+    };
+    match client.get_parameters(telemetry::traced_request(ParametersRequest {})).await {
+        Ok(response) => {
+            let parameters = response.into_inner();
+            let mut lines = vec![format!("Weights of Neuron {} at version {}:", args[0], parameters.version)];
+            for (i, weight) in parameters.weights.iter().enumerate() {
+                lines.push(format!("synapse {}: {}", i, weight));
+            }
+            lines.push(format!("bias: {}", parameters.bias));
+            lines.join("\n")
+        }
+        Err(status) => format!("Failed to read parameters of Neuron {}: {}", args[0], status.message()),
+    }
+}
+
+async fn handle_help(&self) -> String {
     r#"Available commands:
 /neuron_status - Get status of all Neurons
 /neuron_metrics <neuron_id> - Get metrics of a specific Neuron
+/modulate [<name> <level>] - Show or set neuromodulator levels
+/topology - Show the loaded network topology
+/neurons - List registered Neurons
+/weights <neuron_id> - Show the weights of a registered Neuron
+/restart <neuron_id> - Restart a managed Neuron
+/stop <neuron_id> - Stop a managed Neuron
+/alerts - List firing alerts
+/silence [<rule|neuron_id> <minutes>] - Show silences or mute alerts; 0 minutes unmutes
 /help - Show this help message"#
 .to_string()
 }
@@ -93,20 +470,24 @@ async fn handle_help(&mut self) -> String {
 
 #[tonic::async_trait]
 impl SupervisorTrait for Supervisor {
+type SubscribeNeuromodulatorsStream = ReceiverStream<Result<NeuromodulatorLevels, Status>>;
+
 async fn report_neuron_status(
 &self,
 request: Request<SupervisorRequest>,
 ) -> Result<Response<SupervisorResponse>, Status> {
+let context = telemetry::server_context("report_neuron_status", &request);
 let SupervisorRequest {
 neuron_id,
 status,
 ..
 } = request.into_inner();
-self.neuron_status_sender
-.send((neuron_id, status))
-.await
-.expect("Failed to send neuron status");
+context
+.run(async {
+self.process_neuron_status(neuron_id, status);
 Ok(Response::new(SupervisorResponse {}))
+})
+.await
 }
 
 
@@ -114,52 +495,212 @@ async fn report_neuron_metrics(
     &self,
     request: Request<SupervisorRequest>,
 ) -> Result<Response<SupervisorResponse>, Status> {
+    let context = telemetry::server_context("report_neuron_metrics", &request);
     let SupervisorRequest {
         neuron_id,
         metrics,
         ..
     } = request.into_inner();
-    let metrics: HashMap<String, f64> =
-        serde_json::from_str(&metrics).expect("Failed to deserialize metrics");
-    self.neuron_metrics.insert(neuron_id, metrics);
-    Ok(Response::new(SupervisorResponse {}))
+    context
+        .run(async {
+            let metrics: HashMap<String, f64> = serde_json::from_str(&metrics)
+                .map_err(|e| Status::invalid_argument(format!("Invalid metrics: {}", e)))?;
+            self.process_neuron_metrics(neuron_id, metrics);
+            Ok(Response::new(SupervisorResponse {}))
+        })
+        .await
 }
 
 async fn get_neuron_status(
     &self,
-    _request: Request<SupervisorStatusRequest>,
+    request: Request<SupervisorStatusRequest>,
 ) -> Result<Response<SupervisorStatusResponse>, Status> {
-    let neuron_status = self.neuron_status.clone();
-    Ok(Response::new(SupervisorStatusResponse { neuron_status }))
+    let context = telemetry::server_context("get_neuron_status", &request);
+    context
+        .run(async {
+            let neuron_status = self.state.neuron_status();
+            Ok(Response::new(SupervisorStatusResponse { neuron_status }))
+        })
+        .await
 }
 
 async fn get_neuron_metrics(
     &self,
     request: Request<SupervisorMetricsRequest>,
 ) -> Result<Response<SupervisorMetricsResponse>, Status> {
+    let context = telemetry::server_context("get_neuron_metrics", &request);
     let SupervisorMetricsRequest { neuron_id } = request.into_inner();
-    let metrics = self
-        .neuron_metrics
-        .get(&neuron_id)
-        .cloned()
-        .unwrap_or_default();
-    Ok(Response::new(SupervisorMetricsResponse { metrics }))
+    context
+        .run(async {
+            let metrics = self.state.neuron_metrics(&neuron_id).unwrap_or_default();
+            Ok(Response::new(SupervisorMetricsResponse { metrics }))
+        })
+        .await
 }
 
 async fn process_telegram_command(
     &self,
     request: Request<SupervisorRequest>,
 ) -> Result<Response<SupervisorResponse>, Status> {
+    let context = telemetry::server_context("process_telegram_command", &request);
     let SupervisorRequest {
         command,
         args,
         ..
     } = request.into_inner();
-    self.telegram_bot_sender
-        .send((command.clone(), args.clone()))
-        .await
-        .expect("Failed to send Telegram command");
+    context.run(self.handle_telegram_command(command, args)).await;
     Ok(Response::new(SupervisorResponse {}))
 }
+
+async fn set_neuromodulator(
+    &self,
+    request: Request<NeuromodulatorUpdate>,
+) -> Result<Response<SupervisorResponse>, Status> {
+    let context = telemetry::server_context("set_neuromodulator", &request);
+    let NeuromodulatorUpdate { name, level } = request.into_inner();
+    context
+        .run(async {
+            if name.is_empty() {
+                return Err(Status::invalid_argument("Neuromodulator name must not be empty"));
+            }
+            log::info!("Setting neuromodulator {} to {}", name, level);
+            self.state.set_neuromodulator_level(name, level);
+            Ok(Response::new(SupervisorResponse {}))
+        })
+        .await
+}
+
+async fn subscribe_neuromodulators(
+    &self,
+    request: Request<NeuromodulatorSubscription>,
+) -> Result<Response<Self::SubscribeNeuromodulatorsStream>, Status> {
+    let context = telemetry::server_context("subscribe_neuromodulators", &request);
+    let NeuromodulatorSubscription { neuron_id } = request.into_inner();
+    let mut levels = self.state.subscribe_neuromodulators();
+    let (sender, receiver) = mpsc::channel(8);
+    // The subscription outlives the call, so its task carries the span.
+    tokio::spawn(context.run(async move {
+        log::info!("Neuron {} subscribed to neuromodulators", neuron_id);
+        loop {
+            let current = levels.borrow_and_update().clone();
+            if sender
+                .send(Ok(NeuromodulatorLevels { levels: current }))
+                .await
+                .is_err()
+            {
+                break;
+            }
+            if levels.changed().await.is_err() {
+                break;
+            }
+        }
+        log::info!("Neuron {} unsubscribed from neuromodulators", neuron_id);
+    }));
+    Ok(Response::new(ReceiverStream::new(receiver)))
+}
+
+async fn register_neuron(
+    &self,
+    request: Request<NeuronRegistration>,
+) -> Result<Response<RegistrationResponse>, Status> {
+    let context = telemetry::server_context("register_neuron", &request);
+    let registration = request.into_inner();
+    context
+        .run(async {
+            if registration.neuron_id.is_empty() {
+                return Err(Status::invalid_argument("Neuron id must not be empty"));
+            }
+            let neuron_id = registration.neuron_id.clone();
+            log::info!(
+                "Registering Neuron {} at {} ({} inputs, {}, extensions {:?})",
+                neuron_id,
+                registration.address,
+                registration.num_inputs,
+                registration.activation,
+                registration.extensions
+            );
+            self.registry.register(registration.into(), Instant::now());
+            self.process_neuron_status(neuron_id, "Registered".to_string());
+            Ok(Response::new(RegistrationResponse {
+                lease_ms: self.registry.lease().as_millis() as u64,
+                heartbeat_interval_ms: self.registry.heartbeat_interval().as_millis() as u64,
+            }))
+        })
+        .await
+}
+
+async fn heartbeat(
+    &self,
+    request: Request<HeartbeatRequest>,
+) -> Result<Response<HeartbeatResponse>, Status> {
+    let context = telemetry::server_context("heartbeat", &request);
+    let HeartbeatRequest { neuron_id } = request.into_inner();
+    context
+        .run(async {
+            let registered = self.registry.heartbeat(&neuron_id, Instant::now());
+            if registered && self.state.status_of(&neuron_id).as_deref() == Some("Unreachable") {
+                self.process_neuron_status(neuron_id, "Reachable".to_string());
+            }
+            Ok(Response::new(HeartbeatResponse { registered }))
+        })
+        .await
+}
+
+async fn deregister_neuron(
+    &self,
+    request: Request<DeregistrationRequest>,
+) -> Result<Response<SupervisorResponse>, Status> {
+    let context = telemetry::server_context("deregister_neuron", &request);
+    let DeregistrationRequest { neuron_id } = request.into_inner();
+    context
+        .run(async {
+            if self.registry.deregister(&neuron_id) {
+                log::info!("Neuron {} deregistered", neuron_id);
+                self.state.remove_neuron(&neuron_id);
+            }
+            Ok(Response::new(SupervisorResponse {}))
+        })
+        .await
 }
 
+async fn query_metrics(
+    &self,
+    request: Request<MetricsQuery>,
+) -> Result<Response<MetricsQueryResponse>, Status> {
+    let context = telemetry::server_context("query_metrics", &request);
+    let MetricsQuery {
+        neuron_id,
+        metrics,
+        start_ms,
+        end_ms,
+        bucket_ms,
+    } = request.into_inner();
+    context
+        .run(async {
+            let end_ms = if end_ms == 0 { spiking::now_ms() } else { end_ms };
+            if start_ms > end_ms {
+                return Err(Status::invalid_argument("start_ms is after end_ms"));
+            }
+            let series = self
+                .state
+                .query_metrics(&neuron_id, &metrics, start_ms, end_ms, bucket_ms)
+                .into_iter()
+                .map(|(metric, buckets)| MetricSeries {
+                    metric,
+                    buckets: buckets
+                        .into_iter()
+                        .map(|bucket| MetricBucket {
+                            start_ms: bucket.start_ms,
+                            min: bucket.min,
+                            max: bucket.max,
+                            avg: bucket.avg,
+                            count: bucket.count,
+                        })
+                        .collect(),
+                })
+                .collect();
+            Ok(Response::new(MetricsQueryResponse { series }))
+        })
+        .await
+}
+}
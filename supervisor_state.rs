@@ -0,0 +1,156 @@
+// supervisor_state.rs
+use crate::metrics_exporter::NeuronSnapshot;
+use crate::metrics_history::{Bucket, MetricsHistory};
+use crate::neuromodulation::NeuromodulatorLevels;
+use crate::spiking;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::watch;
+
+#[derive(Debug, Default)]
+struct Neurons {
+    status: HashMap<String, String>,
+    metrics: HashMap<String, HashMap<String, f64>>,
+}
+
+/// What the supervisor knows about its neurons, shared by the RPC handlers,
+/// the Telegram command handler and the background tasks. Locks are never
+/// held across an `.await`.
+pub struct SupervisorState {
+    neurons: Mutex<Neurons>,
+    history: Mutex<MetricsHistory>,
+    neuromodulator_levels: Mutex<NeuromodulatorLevels>,
+    neuromodulator_sender: watch::Sender<NeuromodulatorLevels>,
+}
+
+impl SupervisorState {
+    pub fn new() -> Self {
+        Self::with_history(MetricsHistory::default())
+    }
+
+    pub fn with_history(history: MetricsHistory) -> Self {
+        let (neuromodulator_sender, _) = watch::channel(HashMap::new());
+        Self {
+            neurons: Mutex::new(Neurons::default()),
+            history: Mutex::new(history),
+            neuromodulator_levels: Mutex::new(HashMap::new()),
+            neuromodulator_sender,
+        }
+    }
+
+    pub fn record_status(&self, neuron_id: String, status: String) {
+        let mut neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        neurons.status.insert(neuron_id, status);
+    }
+
+    pub fn record_metrics(&self, neuron_id: String, metrics: HashMap<String, f64>) {
+        self.history
+            .lock()
+            .expect("Metrics history lock poisoned")
+            .record(&neuron_id, &metrics, spiking::now_ms());
+        let mut neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        neurons.metrics.insert(neuron_id, metrics);
+    }
+
+    /// Forgets the latest status and metrics of a neuron that left; its
+    /// metrics history ages out with the retention.
+    pub fn remove_neuron(&self, neuron_id: &str) {
+        let mut neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        neurons.status.remove(neuron_id);
+        neurons.metrics.remove(neuron_id);
+    }
+
+    /// Forgets the latest metrics of a neuron that stopped reporting, so that
+    /// nothing keeps evaluating them as if they were current.
+    pub fn clear_metrics(&self, neuron_id: &str) {
+        let mut neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        neurons.metrics.remove(neuron_id);
+    }
+
+    pub fn neuron_status(&self) -> HashMap<String, String> {
+        let neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        neurons.status.clone()
+    }
+
+    pub fn status_of(&self, neuron_id: &str) -> Option<String> {
+        let neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        neurons.status.get(neuron_id).cloned()
+    }
+
+    pub fn neuron_metrics(&self, neuron_id: &str) -> Option<HashMap<String, f64>> {
+        let neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        neurons.metrics.get(neuron_id).cloned()
+    }
+
+    /// Latest status and metrics of every neuron heard from.
+    pub fn snapshot(&self) -> Vec<NeuronSnapshot> {
+        let neurons = self.neurons.lock().expect("Supervisor state lock poisoned");
+        let mut ids: Vec<&String> = neurons.status.keys().chain(neurons.metrics.keys()).collect();
+        ids.sort();
+        ids.dedup();
+        ids.into_iter()
+            .map(|neuron_id| NeuronSnapshot {
+                neuron_id: neuron_id.clone(),
+                status: neurons.status.get(neuron_id).cloned(),
+                metrics: neurons.metrics.get(neuron_id).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Downsampled history of the given metrics of `neuron_id`, or of all of
+    /// its metrics if `metrics` is empty. Samples past the retention are
+    /// dropped first, even if the neuron no longer reports.
+    pub fn query_metrics(
+        &self,
+        neuron_id: &str,
+        metrics: &[String],
+        start_ms: u64,
+        end_ms: u64,
+        bucket_ms: u64,
+    ) -> Vec<(String, Vec<Bucket>)> {
+        let mut history = self.history.lock().expect("Metrics history lock poisoned");
+        history.prune(spiking::now_ms());
+        let names = if metrics.is_empty() {
+            history.metric_names(neuron_id)
+        } else {
+            metrics.to_vec()
+        };
+        names
+            .into_iter()
+            .map(|metric| {
+                let buckets = history.query(neuron_id, &metric, start_ms, end_ms, bucket_ms);
+                (metric, buckets)
+            })
+            .collect()
+    }
+
+    /// Sets a neuromodulator level and broadcasts the full set of levels to all subscribed neurons.
+    pub fn set_neuromodulator_level(&self, name: String, level: f32) {
+        let mut levels = self
+            .neuromodulator_levels
+            .lock()
+            .expect("Neuromodulator lock poisoned");
+        levels.insert(name, level);
+        // Unlike `send`, stores the levels even while no neuron is subscribed,
+        // so that later subscribers start from them.
+        self.neuromodulator_sender.send_replace(levels.clone());
+    }
+
+    pub fn neuromodulator_levels(&self) -> NeuromodulatorLevels {
+        self.neuromodulator_levels
+            .lock()
+            .expect("Neuromodulator lock poisoned")
+            .clone()
+    }
+
+    /// A receiver that starts at the current levels.
+    pub fn subscribe_neuromodulators(&self) -> watch::Receiver<NeuromodulatorLevels> {
+        self.neuromodulator_sender.subscribe()
+    }
+}
+
+impl Default for SupervisorState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,197 @@
+// synapse.rs
+use crate::proto::neuron_service_client::NeuronServiceClient;
+use crate::proto::SynapticInput;
+use crate::telemetry;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+use thiserror::Error;
+use tracing::Instrument;
+use tonic::transport::{Channel, Endpoint};
+use tonic::Status;
+
+const PENDING_WAVE_CAPACITY: usize = 1024;
+
+#[derive(Error, Debug)]
+pub enum SynapseError {
+    #[error("Unknown upstream neuron {0}")]
+    UnknownSource(String),
+    #[error("Slot {slot} is out of range for {size} inputs")]
+    SlotOutOfRange { slot: usize, size: usize },
+    #[error("Slot {0} is fed by more than one upstream neuron")]
+    DuplicateSlot(usize),
+    #[error("Upstream neuron {0} is connected more than once")]
+    DuplicateSource(String),
+    #[error("Synaptic input from {0} has no wave id")]
+    MissingWaveId(String),
+    #[error("Upstream neuron {source_id} sent slot {received}, expected slot {expected}")]
+    SlotMismatch {
+        source_id: String,
+        expected: usize,
+        received: usize,
+    },
+    #[error("Invalid endpoint {0}: {1}")]
+    InvalidEndpoint(String, String),
+}
+
+impl From<SynapseError> for Status {
+    fn from(e: SynapseError) -> Self {
+        Status::invalid_argument(e.to_string())
+    }
+}
+
+/// An incoming connection: `source_id`'s activation lands in input `slot`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpstreamSynapse {
+    pub source_id: String,
+    pub slot: usize,
+}
+
+/// An outgoing connection to input `slot` of the neuron served at `endpoint`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DownstreamSynapse {
+    pub target_id: String,
+    pub endpoint: String,
+    pub slot: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SynapseConfig {
+    #[serde(default)]
+    pub upstream: Vec<UpstreamSynapse>,
+    #[serde(default)]
+    pub downstream: Vec<DownstreamSynapse>,
+}
+
+/// Inputs collected so far for one propagation wave.
+#[derive(Debug)]
+struct PendingWave {
+    values: Vec<f32>,
+    arrived: HashSet<String>,
+}
+
+/// A neuron's place in the network: which upstream neurons it waits for
+/// before firing, and which downstream neurons it forwards its activation to.
+pub struct Synapses {
+    upstream: HashMap<String, usize>,
+    input_size: usize,
+    downstream: Vec<(DownstreamSynapse, NeuronServiceClient<Channel>)>,
+    pending: Mutex<(HashMap<String, PendingWave>, VecDeque<String>)>,
+}
+
+impl Synapses {
+    /// Validates `config` against the neuron's `input_size` and prepares lazy
+    /// connections to the downstream neurons.
+    pub fn connect(config: SynapseConfig, input_size: usize) -> Result<Self, SynapseError> {
+        let mut upstream = HashMap::new();
+        let mut slots = HashSet::new();
+        for synapse in config.upstream {
+            if synapse.slot >= input_size {
+                return Err(SynapseError::SlotOutOfRange {
+                    slot: synapse.slot,
+                    size: input_size,
+                });
+            }
+            if !slots.insert(synapse.slot) {
+                return Err(SynapseError::DuplicateSlot(synapse.slot));
+            }
+            if upstream.contains_key(&synapse.source_id) {
+                return Err(SynapseError::DuplicateSource(synapse.source_id));
+            }
+            upstream.insert(synapse.source_id, synapse.slot);
+        }
+
+        let mut downstream = Vec::new();
+        for synapse in config.downstream {
+            let channel = Endpoint::from_shared(synapse.endpoint.clone())
+                .map_err(|e| SynapseError::InvalidEndpoint(synapse.endpoint.clone(), e.to_string()))?
+                .connect_lazy()
+                .map_err(|e| SynapseError::InvalidEndpoint(synapse.endpoint.clone(), e.to_string()))?;
+            downstream.push((synapse, NeuronServiceClient::new(channel)));
+        }
+
+        Ok(Self {
+            upstream,
+            input_size,
+            downstream,
+            pending: Mutex::new((HashMap::new(), VecDeque::new())),
+        })
+    }
+
+    /// Records `value` from `source_id` for `wave_id`. Returns the assembled
+    /// input vector once every upstream neuron has contributed to the wave.
+    pub fn receive(
+        &self,
+        wave_id: &str,
+        source_id: &str,
+        slot: usize,
+        value: f32,
+    ) -> Result<Option<Vec<f32>>, SynapseError> {
+        // Without a shared id, inputs of one wave could never be matched up.
+        if wave_id.is_empty() {
+            return Err(SynapseError::MissingWaveId(source_id.to_string()));
+        }
+        let expected = *self
+            .upstream
+            .get(source_id)
+            .ok_or_else(|| SynapseError::UnknownSource(source_id.to_string()))?;
+        if slot != expected {
+            return Err(SynapseError::SlotMismatch {
+                source_id: source_id.to_string(),
+                expected,
+                received: slot,
+            });
+        }
+
+        let mut guard = self.pending.lock().expect("Pending wave lock poisoned");
+        let (waves, order) = &mut *guard;
+        let input_size = self.input_size;
+        let wave = waves.entry(wave_id.to_string()).or_insert_with(|| {
+            order.push_back(wave_id.to_string());
+            PendingWave {
+                values: vec![0.0; input_size],
+                arrived: HashSet::new(),
+            }
+        });
+        wave.values[slot] = value;
+        wave.arrived.insert(source_id.to_string());
+
+        if wave.arrived.len() == self.upstream.len() {
+            let wave = waves.remove(wave_id).expect("Pending wave disappeared");
+            order.retain(|id| id != wave_id);
+            return Ok(Some(wave.values));
+        }
+
+        while order.len() > PENDING_WAVE_CAPACITY {
+            if let Some(oldest) = order.pop_front() {
+                log::warn!("Dropping incomplete wave {}", oldest);
+                waves.remove(&oldest);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Forwards `value` to every downstream neuron without waiting for them.
+    pub fn propagate(&self, source_id: &str, wave_id: &str, value: f32, timestamp_ms: u64) {
+        for (synapse, client) in &self.downstream {
+            let mut client = client.clone();
+            let target_id = synapse.target_id.clone();
+            let input = SynapticInput {
+                source_id: source_id.to_string(),
+                slot: synapse.slot as u32,
+                value,
+                wave_id: wave_id.to_string(),
+                timestamp_ms,
+            };
+            let request = telemetry::traced_request(input);
+            tokio::spawn(
+                async move {
+                    if let Err(e) = client.receive_synaptic_input(request).await {
+                        log::error!("Failed to forward activation to Neuron {}: {}", target_id, e);
+                    }
+                }
+                .in_current_span(),
+            );
+        }
+    }
+}
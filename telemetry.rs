@@ -0,0 +1,187 @@
+// telemetry.rs
+use std::future::Future;
+use thiserror::Error;
+use tonic::metadata::MetadataValue;
+use tonic::Request;
+use tracing::{Instrument, Span};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Metadata key carrying the id that correlates everything done for one input.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+#[derive(Error, Debug)]
+pub enum TelemetryError {
+    #[error("Failed to install tracing subscriber: {0}")]
+    Subscriber(String),
+    #[cfg(feature = "otlp")]
+    #[error("Failed to install OTLP exporter: {0}")]
+    Otlp(#[from] opentelemetry::trace::TraceError),
+}
+
+/// Installs the global subscriber: formatted output filtered by `RUST_LOG`
+/// (records from the `log` macros included) and, when built with the `otlp`
+/// feature and `OTEL_EXPORTER_OTLP_ENDPOINT` is set, an OTLP span exporter.
+#[cfg_attr(not(feature = "otlp"), allow(unused_variables))]
+pub fn init(service_name: &str) -> Result<(), TelemetryError> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("error"));
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer());
+
+    #[cfg(feature = "otlp")]
+    {
+        if let Ok(endpoint) = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            let tracer = otlp::tracer(service_name, endpoint)?;
+            return subscriber
+                .with(tracing_opentelemetry::layer().with_tracer(tracer))
+                .try_init()
+                .map_err(|e| TelemetryError::Subscriber(e.to_string()));
+        }
+    }
+    subscriber
+        .try_init()
+        .map_err(|e| TelemetryError::Subscriber(e.to_string()))
+}
+
+/// Flushes spans that have not been exported yet.
+pub fn shutdown() {
+    #[cfg(feature = "otlp")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+pub fn new_request_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// The request id of the input being handled by the current task, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+}
+
+/// Span and request id an RPC handler runs under.
+pub struct RequestContext {
+    request_id: String,
+    span: Span,
+}
+
+impl RequestContext {
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Runs `future` inside the span, with the request id visible to
+    /// `current_request_id` and `traced_request`.
+    pub async fn run<F: Future>(self, future: F) -> F::Output {
+        let RequestContext { request_id, span } = self;
+        REQUEST_ID.scope(request_id, future.instrument(span)).await
+    }
+}
+
+/// Opens the span for handling `request` in `rpc`, continuing the request id
+/// (and, with `otlp`, the trace) of the caller, or starting a new one. The
+/// ids of the requests whose extension tokens the handler consumes are
+/// recorded later, as `extension_request_ids`.
+pub fn server_context<T>(rpc: &'static str, request: &Request<T>) -> RequestContext {
+    let request_id = request
+        .metadata()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(new_request_id);
+    let span = tracing::info_span!(
+        "rpc",
+        rpc,
+        request_id = %request_id,
+        extension_request_ids = tracing::field::Empty
+    );
+    #[cfg(feature = "otlp")]
+    otlp::set_remote_parent(&span, request.metadata());
+    RequestContext { request_id, span }
+}
+
+/// Wraps `message` for an outgoing call that continues the current request,
+/// or starts a new one outside of a handler.
+pub fn traced_request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    let request_id = current_request_id().unwrap_or_else(new_request_id);
+    if let Ok(value) = MetadataValue::from_str(&request_id) {
+        request.metadata_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    #[cfg(feature = "otlp")]
+    otlp::inject(&Span::current(), request.metadata_mut());
+    request
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::propagation::{Extractor, Injector};
+    use opentelemetry::sdk::propagation::TraceContextPropagator;
+    use opentelemetry::sdk::{trace, Resource};
+    use opentelemetry::trace::TraceError;
+    use opentelemetry::{global, KeyValue};
+    use tonic::metadata::{KeyRef, MetadataKey, MetadataMap, MetadataValue};
+    use tracing::Span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    pub fn tracer(service_name: &str, endpoint: String) -> Result<trace::Tracer, TraceError> {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        opentelemetry_otlp::new_pipeline()
+            .with_endpoint(endpoint)
+            .with_trace_config(trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                service_name.to_string(),
+            )])))
+            .install_batch(opentelemetry::runtime::Tokio)
+    }
+
+    pub fn set_remote_parent(span: &Span, metadata: &MetadataMap) {
+        let parent = global::get_text_map_propagator(|propagator| propagator.extract(&MetadataExtractor(metadata)));
+        span.set_parent(parent);
+    }
+
+    pub fn inject(span: &Span, metadata: &mut MetadataMap) {
+        let context = span.context();
+        global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut MetadataInjector(metadata))
+        });
+    }
+
+    struct MetadataExtractor<'a>(&'a MetadataMap);
+
+    impl Extractor for MetadataExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|value| value.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0
+                .keys()
+                .map(|key| match key {
+                    KeyRef::Ascii(key) => key.as_str(),
+                    KeyRef::Binary(key) => key.as_str(),
+                })
+                .collect()
+        }
+    }
+
+    struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+    impl Injector for MetadataInjector<'_> {
+        fn set(&mut self, key: &str, value: String) {
+            if let (Ok(key), Ok(value)) = (MetadataKey::from_bytes(key.as_bytes()), MetadataValue::from_str(&value)) {
+                self.0.insert(key, value);
+            }
+        }
+    }
+}
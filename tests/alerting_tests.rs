@@ -0,0 +1,174 @@
+// tests/alerting_tests.rs
+use neurox::alerting::{parse_rules, AlertEngine, AlertError, AlertState, Comparison, Condition};
+use neurox::metrics_exporter::NeuronSnapshot;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const RULES: &str = r#"
+[[rules]]
+name = "slow"
+kind = "metric"
+metric = "latency_p99"
+comparison = "above"
+threshold = 0.5
+for_secs = 300
+
+[[rules]]
+name = "unreachable"
+kind = "status"
+status = "Unreachable"
+neurons = ["n1"]
+"#;
+
+fn snapshot(neuron_id: &str, status: &str, latency_p99: f64) -> NeuronSnapshot {
+    let mut metrics = HashMap::new();
+    metrics.insert("latency_p99".to_string(), latency_p99);
+    NeuronSnapshot {
+        neuron_id: neuron_id.to_string(),
+        status: Some(status.to_string()),
+        metrics,
+    }
+}
+
+#[test]
+fn test_parse_rules() {
+    let rules = parse_rules(RULES).unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(
+        rules[0].condition,
+        Condition::Metric {
+            metric: "latency_p99".to_string(),
+            comparison: Comparison::Above,
+            threshold: 0.5,
+        }
+    );
+    assert_eq!(rules[0].for_secs, 300);
+    assert_eq!(rules[1].neurons, vec!["n1".to_string()]);
+
+    let duplicated = format!("{}\n[[rules]]\nname = \"slow\"\nkind = \"status\"\nstatus = \"Idle\"\n", RULES);
+    assert!(matches!(parse_rules(&duplicated), Err(AlertError::DuplicateRule(name)) if name == "slow"));
+}
+
+#[test]
+fn test_alert_fires_after_duration_once_and_resolves() {
+    let mut engine = AlertEngine::new(parse_rules(RULES).unwrap());
+    let start = Instant::now();
+    let slow = [snapshot("n1", "Idle", 0.8)];
+
+    assert!(engine.evaluate(&slow, start).is_empty());
+    assert!(engine.evaluate(&slow, start + Duration::from_secs(299)).is_empty());
+
+    let notifications = engine.evaluate(&slow, start + Duration::from_secs(300));
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].rule, "slow");
+    assert_eq!(notifications[0].neuron_id, "n1");
+    assert_eq!(notifications[0].state, AlertState::Firing);
+    assert!(engine.evaluate(&slow, start + Duration::from_secs(400)).is_empty());
+    assert_eq!(engine.firing().len(), 1);
+
+    let notifications = engine.evaluate(&[snapshot("n1", "Idle", 0.1)], start + Duration::from_secs(500));
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].state, AlertState::Resolved);
+    assert!(engine.firing().is_empty());
+}
+
+#[test]
+fn test_alert_duration_restarts_when_condition_clears() {
+    let mut engine = AlertEngine::new(parse_rules(RULES).unwrap());
+    let start = Instant::now();
+
+    engine.evaluate(&[snapshot("n1", "Idle", 0.8)], start);
+    engine.evaluate(&[snapshot("n1", "Idle", 0.1)], start + Duration::from_secs(200));
+    engine.evaluate(&[snapshot("n1", "Idle", 0.8)], start + Duration::from_secs(250));
+    assert!(engine
+        .evaluate(&[snapshot("n1", "Idle", 0.8)], start + Duration::from_secs(400))
+        .is_empty());
+    assert_eq!(
+        engine
+            .evaluate(&[snapshot("n1", "Idle", 0.8)], start + Duration::from_secs(550))
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_status_rule_applies_only_to_listed_neurons() {
+    let mut engine = AlertEngine::new(parse_rules(RULES).unwrap());
+    let notifications = engine.evaluate(
+        &[snapshot("n1", "Unreachable", 0.0), snapshot("n2", "Unreachable", 0.0)],
+        Instant::now(),
+    );
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].rule, "unreachable");
+    assert_eq!(notifications[0].neuron_id, "n1");
+}
+
+#[test]
+fn test_silence_suppresses_notifications_until_it_expires() {
+    let mut engine = AlertEngine::new(parse_rules(RULES).unwrap());
+    let start = Instant::now();
+    engine.silence("n1".to_string(), Duration::from_secs(60), start).unwrap();
+    assert_eq!(engine.silences(start), vec![("n1".to_string(), Duration::from_secs(60))]);
+
+    assert!(engine.evaluate(&[snapshot("n1", "Unreachable", 0.0)], start).is_empty());
+    assert_eq!(engine.firing().len(), 1);
+
+    // Still firing after the silence ends, so there is nothing new to say.
+    assert!(engine
+        .evaluate(&[snapshot("n1", "Unreachable", 0.0)], start + Duration::from_secs(61))
+        .is_empty());
+    assert!(engine.silences(start + Duration::from_secs(61)).is_empty());
+    let notifications = engine.evaluate(&[snapshot("n1", "Idle", 0.0)], start + Duration::from_secs(62));
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].state, AlertState::Resolved);
+
+    engine.silence("unreachable".to_string(), Duration::from_secs(60), start).unwrap();
+    engine.silence("unreachable".to_string(), Duration::from_secs(0), start).unwrap();
+    assert!(engine.silences(start).is_empty());
+}
+
+#[test]
+fn test_silence_past_the_end_of_time_is_rejected() {
+    let mut engine = AlertEngine::new(parse_rules(RULES).unwrap());
+    let start = Instant::now();
+    let result = engine.silence("n1".to_string(), Duration::from_secs(u64::MAX), start);
+    assert!(matches!(result, Err(AlertError::SilenceTooLong(_, _))));
+    assert!(engine.silences(start).is_empty());
+}
+
+#[test]
+fn test_vanished_neuron_alerts_are_resolved() {
+    let mut engine = AlertEngine::new(parse_rules(RULES).unwrap());
+    let start = Instant::now();
+    engine.evaluate(&[snapshot("n1", "Unreachable", 0.8)], start);
+    assert_eq!(engine.firing().len(), 1);
+
+    let notifications = engine.evaluate(&[], start + Duration::from_secs(1));
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].state, AlertState::Resolved);
+    assert_eq!(notifications[0].neuron_id, "n1");
+    assert!(notifications[0].message.contains("gone"));
+    assert!(engine.firing().is_empty());
+
+    // Back under the same id, the slow rule waits its full duration again.
+    assert!(engine
+        .evaluate(&[snapshot("n1", "Idle", 0.8)], start + Duration::from_secs(300))
+        .is_empty());
+    assert_eq!(
+        engine
+            .evaluate(&[snapshot("n1", "Idle", 0.8)], start + Duration::from_secs(600))
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_silenced_vanished_neuron_resolves_quietly() {
+    let mut engine = AlertEngine::new(parse_rules(RULES).unwrap());
+    let start = Instant::now();
+    engine.evaluate(&[snapshot("n1", "Unreachable", 0.8)], start);
+    engine.silence("n1".to_string(), Duration::from_secs(60), start).unwrap();
+
+    assert!(engine.evaluate(&[], start + Duration::from_secs(1)).is_empty());
+    assert!(engine.firing().is_empty());
+}
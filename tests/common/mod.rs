@@ -0,0 +1,38 @@
+// tests/common/mod.rs
+#![allow(dead_code)]
+use neurox::activation::Activation;
+use neurox::database::NeuronDb;
+use neurox::input_schema::InputSchema;
+use neurox::neuron::{Neuron, NeuronError, ResizePolicy};
+use neurox::weight_init::XavierUniform;
+use tempfile::TempDir;
+
+/// A database of its own for each test, removed with the returned directory.
+pub fn temp_db() -> (TempDir, NeuronDb) {
+    let dir = TempDir::new().unwrap();
+    let db = NeuronDb::new(dir.path()).unwrap();
+    (dir, db)
+}
+
+/// Opens a neuron on `db` with Xavier-initialized weights and none of the
+/// optional extensions, refusing stored weights of another dimension.
+pub fn open_neuron(
+    neuron_id: String,
+    db: NeuronDb,
+    input_schema: InputSchema,
+    activation: Box<dyn Activation>,
+) -> Result<Neuron, NeuronError> {
+    Neuron::open_with_db(
+        neuron_id,
+        db,
+        input_schema,
+        activation,
+        &XavierUniform,
+        ResizePolicy::Refuse,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+}
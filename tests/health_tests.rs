@@ -0,0 +1,51 @@
+// tests/health_tests.rs
+use neurox::health;
+use neurox::neuron::SharedNeuron;
+use neurox::proto::neuron_service_server::NeuronServiceServer;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::transport::Server;
+use tonic_health::proto::health_check_response::ServingStatus;
+use tonic_health::proto::health_client::HealthClient;
+use tonic_health::proto::HealthCheckRequest;
+
+async fn status(client: &mut HealthClient<tonic::transport::Channel>) -> i32 {
+    client
+        .check(HealthCheckRequest {
+            service: "neuron.NeuronService".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .status
+}
+
+#[tokio::test]
+async fn test_health_follows_readiness() {
+    let ready = Arc::new(AtomicBool::new(false));
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let check = ready.clone();
+    tokio::spawn(health::report_readiness::<NeuronServiceServer<SharedNeuron>, _>(
+        health_reporter,
+        move || check.load(Ordering::Relaxed),
+    ));
+    tokio::spawn(
+        Server::builder()
+            .add_service(health_service)
+            .add_service(health::reflection_service())
+            .serve("127.0.0.1:50161".parse().unwrap()),
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let mut client = HealthClient::connect("http://127.0.0.1:50161").await.unwrap();
+    assert_eq!(status(&mut client).await, ServingStatus::NotServing as i32);
+
+    ready.store(true, Ordering::Relaxed);
+    tokio::time::sleep(health::CHECK_INTERVAL * 2).await;
+    assert_eq!(status(&mut client).await, ServingStatus::Serving as i32);
+
+    ready.store(false, Ordering::Relaxed);
+    tokio::time::sleep(health::CHECK_INTERVAL * 2).await;
+    assert_eq!(status(&mut client).await, ServingStatus::NotServing as i32);
+}
@@ -11,6 +11,8 @@ async fn test_neuron_service() {
 
     let input_signal = InputSignal {
         values: vec![1.0; 10],
+        request_id: String::new(),
+        timestamp_ms: 0,
     };
     let request = Request::new(input_signal);
     let response = client.process_input(request).await.unwrap();
@@ -20,12 +22,15 @@ async fn test_neuron_service() {
 
     let weight_update = WeightUpdate {
         deltas: vec![0.1; 10],
+        bias_delta: 0.0,
     };
     let request = Request::new(weight_update);
     client.update_weights(request).await.unwrap();
 
     let input_signal = InputSignal {
         values: vec![1.0; 10],
+        request_id: String::new(),
+        timestamp_ms: 0,
     };
     let request = Request::new(input_signal);
     let response = client.process_input(request).await.unwrap();
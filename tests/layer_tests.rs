@@ -0,0 +1,147 @@
+// tests/layer_tests.rs
+use neurox::database::NeuronDb;
+use neurox::input_schema::InputSchema;
+use neurox::layer::Layer;
+use neurox::neuron::ResizePolicy;
+use neurox::proto::layer_service_server::LayerService;
+use neurox::proto::{InputSignal, LayerNeuronsRequest, NeuronInput, NeuronRef};
+use neurox::weight_init::XavierUniform;
+use tempfile::TempDir;
+use tonic::{Code, Request};
+
+#[test]
+fn test_scoped_db_isolates_neurons() {
+    let dir = TempDir::new().unwrap();
+    let db = NeuronDb::new(dir.path()).unwrap();
+    let a = db.scoped("a");
+    let b = db.scoped("b");
+
+    a.put_f32s(b"weights", &[1.0, 2.0]).unwrap();
+    b.put_f32s(b"weights", &[3.0]).unwrap();
+
+    assert_eq!(a.get_f32s(b"weights").unwrap(), Some(vec![1.0, 2.0]));
+    assert_eq!(b.get_f32s(b"weights").unwrap(), Some(vec![3.0]));
+    assert_eq!(db.get_f32s(b"weights").unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_layer_processes_input_with_every_neuron() {
+    let layer = Layer::open(
+        "test_layer".to_string(),
+        4,
+        InputSchema::fixed(3),
+        "relu",
+        &XavierUniform,
+        ResizePolicy::Reinitialize,
+    )
+    .unwrap();
+
+    let neurons = layer
+        .list_neurons(Request::new(LayerNeuronsRequest {}))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(neurons.neuron_ids.len(), 4);
+
+    let output = layer
+        .process_layer_input(Request::new(InputSignal {
+            values: vec![0.5, -0.5, 1.0],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(output.values.len(), 4);
+    assert_eq!(output.weight_versions.len(), 4);
+
+    let parameters = layer
+        .get_neuron_parameters(Request::new(NeuronRef {
+            neuron_id: neurons.neuron_ids[0].clone(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(parameters.weights.len(), 3);
+}
+
+#[tokio::test]
+async fn test_layer_rejects_unknown_neuron_and_wrong_size() {
+    let layer = Layer::open(
+        "test_layer_errors".to_string(),
+        2,
+        InputSchema::fixed(2),
+        "sigmoid",
+        &XavierUniform,
+        ResizePolicy::Reinitialize,
+    )
+    .unwrap();
+
+    let status = layer
+        .process_neuron_input(Request::new(NeuronInput {
+            neuron_id: "missing".to_string(),
+            input: Some(InputSignal {
+                values: vec![1.0, 1.0],
+                request_id: String::new(),
+                timestamp_ms: 0,
+            }),
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = layer
+        .process_layer_input(Request::new(InputSignal {
+            values: vec![1.0],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_layer_output_is_one_row_per_neuron() {
+    let layer = Layer::open(
+        "test_layer_matrix".to_string(),
+        3,
+        InputSchema::fixed(2),
+        "sigmoid",
+        &XavierUniform,
+        ResizePolicy::Reinitialize,
+    )
+    .unwrap();
+    let values = vec![0.25, -1.0];
+
+    let output = layer
+        .process_layer_input(Request::new(InputSignal {
+            values: values.clone(),
+            request_id: String::new(),
+            timestamp_ms: 0,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let neurons = layer
+        .list_neurons(Request::new(LayerNeuronsRequest {}))
+        .await
+        .unwrap()
+        .into_inner();
+    for (i, neuron_id) in neurons.neuron_ids.into_iter().enumerate() {
+        let parameters = layer
+            .get_neuron_parameters(Request::new(NeuronRef { neuron_id }))
+            .await
+            .unwrap()
+            .into_inner();
+        let z: f32 = parameters.bias
+            + values
+                .iter()
+                .zip(&parameters.weights)
+                .map(|(value, weight)| value * weight)
+                .sum::<f32>();
+        assert!((output.values[i] - 1.0 / (1.0 + (-z).exp())).abs() < 1e-6);
+        assert_eq!(output.weight_versions[i], parameters.version);
+    }
+}
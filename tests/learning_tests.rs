@@ -0,0 +1,67 @@
+// tests/learning_tests.rs
+use neurox::learning::{Bcm, Hebbian, LearningError, LearningRule, LearningRuleState, LocalLearning, Oja};
+
+#[test]
+fn test_hebbian_deltas() {
+    let mut rule = Hebbian;
+    let deltas = rule.deltas(&[1.0, 0.5, 0.0], 2.0, &[0.0; 3]);
+    assert_eq!(deltas, vec![2.0, 1.0, 0.0]);
+}
+
+#[test]
+fn test_oja_deltas_decay_large_weights() {
+    let mut rule = Oja;
+    let deltas = rule.deltas(&[1.0, 1.0], 1.0, &[0.5, 2.0]);
+    assert_eq!(deltas, vec![0.5, -1.0]);
+}
+
+#[test]
+fn test_bcm_threshold_tracks_activity() {
+    let mut rule = Bcm::new(0.0, 1.0).unwrap();
+    let first = rule.deltas(&[1.0], 1.0, &[0.0]);
+    assert_eq!(first, vec![1.0]);
+    // theta has moved to y^2 = 1.0, so the same activity no longer potentiates.
+    let second = rule.deltas(&[1.0], 1.0, &[0.0]);
+    assert_eq!(second, vec![0.0]);
+}
+
+#[test]
+fn test_bcm_rejects_non_positive_time_constant() {
+    assert_eq!(Bcm::new(0.0, 0.0).unwrap_err(), LearningError::InvalidTimeConstant(0.0));
+    assert!(Bcm::new(0.0, -1.0).is_err());
+    assert!(Bcm::new(0.0, f32::NAN).is_err());
+}
+
+#[test]
+fn test_bcm_threshold_round_trips_through_state() {
+    let learning = LocalLearning::new(Box::new(Bcm::new(0.0, 1.0).unwrap()), 1.0);
+    learning.deltas(&[1.0], 1.0, &[0.0]);
+    let state = learning.state();
+    assert_eq!(state.name, "bcm");
+    assert_eq!(state.values, vec![1.0]);
+
+    // A fresh rule picks up where the stored one left off.
+    let resumed = LocalLearning::new(Box::new(Bcm::new(0.0, 1.0).unwrap()), 1.0);
+    resumed.load_state(state);
+    assert_eq!(resumed.deltas(&[1.0], 1.0, &[0.0]), vec![0.0]);
+
+    // Stateless rules have nothing to store.
+    assert_eq!(
+        Hebbian.state(),
+        LearningRuleState {
+            name: "hebbian".to_string(),
+            values: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn test_local_learning_scales_and_freezes() {
+    let learning = LocalLearning::new(Box::new(Hebbian), 0.1);
+    let deltas = learning.deltas(&[1.0, 2.0], 1.0, &[0.0; 2]);
+    assert_eq!(deltas, vec![0.1, 0.2]);
+
+    assert!(!learning.is_frozen());
+    learning.set_frozen(true);
+    assert!(learning.is_frozen());
+}
@@ -0,0 +1,43 @@
+// tests/metrics_exporter_tests.rs
+use neurox::metrics_exporter::{render, NeuronSnapshot};
+use std::collections::HashMap;
+
+fn snapshot(neuron_id: &str, status: Option<&str>, metrics: &[(&str, f64)]) -> NeuronSnapshot {
+    NeuronSnapshot {
+        neuron_id: neuron_id.to_string(),
+        status: status.map(str::to_string),
+        metrics: metrics
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+#[test]
+fn test_render_groups_metrics_by_family() {
+    let output = render(&[
+        snapshot("n2", Some("Idle"), &[("latency_p50", 0.25)]),
+        snapshot("n1", Some("Unreachable"), &[("latency_p50", 0.5), ("weight_norm", 1.0)]),
+    ]);
+
+    let expected = "\
+# TYPE neurox_latency_p50 gauge
+neurox_latency_p50{neuron_id=\"n1\"} 0.5
+neurox_latency_p50{neuron_id=\"n2\"} 0.25
+# TYPE neurox_weight_norm gauge
+neurox_weight_norm{neuron_id=\"n1\"} 1
+# TYPE neurox_neuron_status gauge
+neurox_neuron_status{neuron_id=\"n1\",status=\"Unreachable\"} 1
+neurox_neuron_status{neuron_id=\"n2\",status=\"Idle\"} 1
+# EOF
+";
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_render_sanitizes_names_and_escapes_labels() {
+    let output = render(&[snapshot("n\"1", None, &[("tokens/sec", 2.0)])]);
+    assert!(output.contains("neurox_tokens_sec{neuron_id=\"n\\\"1\"} 2\n"));
+    assert!(!output.contains("neuron_status"));
+    assert!(output.ends_with("# EOF\n"));
+}
@@ -0,0 +1,58 @@
+// tests/metrics_history_tests.rs
+use neurox::metrics_history::MetricsHistory;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn metrics(processing_time: f64) -> HashMap<String, f64> {
+    let mut metrics = HashMap::new();
+    metrics.insert("processing_time".to_string(), processing_time);
+    metrics
+}
+
+#[test]
+fn test_history_downsamples_into_buckets() {
+    let mut history = MetricsHistory::new(Duration::from_secs(3600), 1024);
+    for (timestamp_ms, value) in &[(1_000, 1.0), (1_500, 3.0), (2_100, 5.0), (2_900, 7.0), (3_200, 9.0)] {
+        history.record("n1", &metrics(*value), *timestamp_ms);
+    }
+
+    let buckets = history.query("n1", "processing_time", 0, 10_000, 1_000);
+    assert_eq!(buckets.len(), 3);
+    assert_eq!(buckets[0].start_ms, 1_000);
+    assert_eq!((buckets[0].min, buckets[0].max, buckets[0].avg), (1.0, 3.0, 2.0));
+    assert_eq!(buckets[1].count, 2);
+    assert_eq!(buckets[2].avg, 9.0);
+
+    // The range is inclusive and raw samples come back with a bucket of 0.
+    let raw = history.query("n1", "processing_time", 1_500, 2_900, 0);
+    assert_eq!(raw.len(), 3);
+    assert!(history.query("n1", "missing", 0, 10_000, 0).is_empty());
+}
+
+#[test]
+fn test_history_applies_retention_and_capacity() {
+    let mut history = MetricsHistory::new(Duration::from_secs(10), 3);
+    for i in 0..5u64 {
+        history.record("n1", &metrics(i as f64), i * 1_000);
+    }
+    // Only the last three samples fit.
+    assert_eq!(history.query("n1", "processing_time", 0, 100_000, 0).len(), 3);
+
+    history.record("n1", &metrics(42.0), 60_000);
+    let samples = history.query("n1", "processing_time", 0, 100_000, 0);
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].avg, 42.0);
+    assert_eq!(history.metric_names("n1"), vec!["processing_time".to_string()]);
+}
+
+#[test]
+fn test_prune_ages_out_neurons_that_stopped_reporting() {
+    let mut history = MetricsHistory::new(Duration::from_secs(10), 16);
+    history.record("n1", &metrics(1.0), 1_000);
+    history.record("n2", &metrics(2.0), 8_000);
+
+    history.prune(15_000);
+    assert!(history.query("n1", "processing_time", 0, 100_000, 0).is_empty());
+    assert!(history.metric_names("n1").is_empty());
+    assert_eq!(history.query("n2", "processing_time", 0, 100_000, 0).len(), 1);
+}
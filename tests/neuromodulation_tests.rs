@@ -0,0 +1,37 @@
+// tests/neuromodulation_tests.rs
+use neurox::neuromodulation::{ModulationBinding, ModulationTarget, Neuromodulation};
+use std::collections::HashMap;
+use tokio::sync::watch;
+
+#[test]
+fn test_modulation_factor_follows_levels() {
+    let bindings = vec![
+        ModulationBinding {
+            modulator: "dopamine".to_string(),
+            target: ModulationTarget::LearningRate,
+            baseline: 1.0,
+            sensitivity: 1.0,
+        },
+        ModulationBinding {
+            modulator: "serotonin".to_string(),
+            target: ModulationTarget::FiringThreshold,
+            baseline: 0.0,
+            sensitivity: 0.5,
+        },
+    ];
+    let (sender, receiver) = watch::channel(HashMap::new());
+    let neuromodulation = Neuromodulation::new(bindings, receiver);
+
+    // With no dopamine and baseline 1.0, learning is switched off entirely.
+    assert_eq!(neuromodulation.factor(ModulationTarget::LearningRate), 0.0);
+    assert_eq!(neuromodulation.factor(ModulationTarget::FiringThreshold), 1.0);
+    assert_eq!(neuromodulation.factor(ModulationTarget::ActivationGain), 1.0);
+
+    let mut levels = HashMap::new();
+    levels.insert("dopamine".to_string(), 2.0);
+    levels.insert("serotonin".to_string(), 2.0);
+    sender.send(levels).unwrap();
+
+    assert_eq!(neuromodulation.factor(ModulationTarget::LearningRate), 2.0);
+    assert_eq!(neuromodulation.factor(ModulationTarget::FiringThreshold), 2.0);
+}
@@ -0,0 +1,46 @@
+// tests/neuron_metrics_tests.rs
+use neurox::neuron_metrics::{NeuronMetrics, NeuronTotals};
+use std::time::Duration;
+
+fn totals(request_count: u64, error_count: u64) -> NeuronTotals {
+    NeuronTotals {
+        request_count,
+        error_count,
+        weight_norm: 2.0,
+        last_update_ms: 1_000,
+    }
+}
+
+#[test]
+fn test_metrics_window_summarizes_outputs() {
+    let metrics = NeuronMetrics::new();
+    for (i, activation) in [0.0, 0.0, 1.0, 3.0].iter().enumerate() {
+        metrics.record_output(Duration::from_millis(10 * (i as u64 + 1)), *activation);
+    }
+    metrics.record_extension_tokens(8);
+
+    let report = metrics.take(totals(5, 1));
+    assert_eq!(report["request_count"], 5.0);
+    assert_eq!(report["requests"], 5.0);
+    assert_eq!(report["errors"], 1.0);
+    assert_eq!(report["activation_mean"], 1.0);
+    assert_eq!(report["activation_variance"], 1.5);
+    assert_eq!(report["dead_fraction"], 0.5);
+    assert_eq!(report["latency_p50"], 0.02);
+    assert_eq!(report["latency_p99"], 0.04);
+    assert_eq!(report["weight_norm"], 2.0);
+    assert!(report["extension_tokens_per_sec"] > 0.0);
+}
+
+#[test]
+fn test_metrics_window_resets_after_take() {
+    let metrics = NeuronMetrics::new();
+    metrics.record_output(Duration::from_millis(5), 1.0);
+    metrics.take(totals(1, 0));
+
+    let report = metrics.take(totals(3, 0));
+    assert_eq!(report["request_count"], 3.0);
+    assert_eq!(report["requests"], 2.0);
+    assert!(!report.contains_key("activation_mean"));
+    assert!(!report.contains_key("latency_p50"));
+}
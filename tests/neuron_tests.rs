@@ -1,12 +1,15 @@
 // tests/neuron_tests.rs
-use neurox::activation::ReLU;
+mod common;
+
+use common::temp_db;
+use neurox::activation::{ReLU, Sigmoid};
 use neurox::database::NeuronDb;
-use neurox::extensions::{EyeExt, WebhookStreamExt, MessengerInExt, MessengerOutExt};
-use neurox::neuron::Neuron;
-use neurox::proto::{InputSignal, OutputSignal, WeightUpdate};
-use neurox::weight_init::XavierUniform;
-use std::collections::HashMap;
-use tokio::sync::mpsc;
+use neurox::input_schema::{ExtensionPolicy, InputSchema, InputSchemaError};
+use neurox::learning::{Bcm, Hebbian, LearningRuleState};
+use neurox::neuron::NeuronError;
+use neurox::proto::{BackwardRequest, InputBatch, InputSignal, ParametersRequest, WeightUpdate};
+use std::sync::Arc;
+use tempfile::TempDir;
 use tonic::Request;
 
 #[tokio::test]
@@ -14,23 +17,14 @@ async fn test_neuron_process_input() {
     let neuron_id = "test_neuron".to_string();
     let num_inputs = 10;
     let activation = Box::new(ReLU);
-    let weight_initializer = XavierUniform;
 
-    let (extension_sender, extension_receiver) = mpsc::channel(32);
-    let neuron = Neuron::new(
-        neuron_id,
-        num_inputs,
-        activation,
-        &weight_initializer,
-        None,
-        None,
-        None,
-        None,
-        Some(extension_receiver),
-    );
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(neuron_id, db, InputSchema::fixed(num_inputs), activation).unwrap();
 
     let input_signal = InputSignal {
         values: vec![1.0; num_inputs],
+        request_id: String::new(),
+        timestamp_ms: 0,
     };
     let request = Request::new(input_signal);
     let response = neuron.process_input(request).await.unwrap();
@@ -44,28 +38,23 @@ async fn test_neuron_update_weights() {
     let neuron_id = "test_neuron".to_string();
     let num_inputs = 10;
     let activation = Box::new(ReLU);
-    let weight_initializer = XavierUniform;
 
-    let (extension_sender, extension_receiver) = mpsc::channel(32);
-    let neuron = Neuron::new(
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(
         neuron_id,
-        num_inputs,
+        db.clone(),
+        InputSchema::fixed(num_inputs),
         activation,
-        &weight_initializer,
-        None,
-        None,
-        None,
-        None,
-        Some(extension_receiver),
-    );
+    )
+    .unwrap();
 
     let weight_update = WeightUpdate {
         deltas: vec![0.1; num_inputs],
+        bias_delta: 0.0,
     };
     let request = Request::new(weight_update);
     neuron.update_weights(request).await.unwrap();
 
-    let db = NeuronDb::new(&neuron_id).unwrap();
     let stored_weights: Vec<f32> = db
         .get(b"weights")
         .unwrap()
@@ -78,4 +67,386 @@ async fn test_neuron_update_weights() {
     for &w in &stored_weights {
         assert!(w > 0.0);
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_neuron_bias_update() {
+    let neuron_id = "test_neuron_bias".to_string();
+    let num_inputs = 10;
+    let activation = Box::new(ReLU);
+
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(
+        neuron_id.clone(),
+        db,
+        InputSchema::fixed(num_inputs),
+        activation,
+    )
+    .unwrap();
+
+    let weight_update = WeightUpdate {
+        deltas: vec![0.0; num_inputs],
+        bias_delta: 0.5,
+    };
+    let request = Request::new(weight_update);
+    neuron.update_weights(request).await.unwrap();
+
+    let request = Request::new(ParametersRequest {});
+    let parameters = neuron.get_parameters(request).await.unwrap().into_inner();
+
+    assert_eq!(parameters.neuron_id, neuron_id);
+    assert_eq!(parameters.weights.len(), num_inputs);
+    assert_eq!(parameters.bias, 0.5);
+}
+
+#[tokio::test]
+async fn test_neuron_rejects_mismatched_deltas() {
+    let neuron_id = "test_neuron_deltas".to_string();
+    let num_inputs = 10;
+    let activation = Box::new(ReLU);
+
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(neuron_id, db, InputSchema::fixed(num_inputs), activation).unwrap();
+
+    let weight_update = WeightUpdate {
+        deltas: vec![0.1; num_inputs + 1],
+        bias_delta: 0.0,
+    };
+    let request = Request::new(weight_update);
+    let status = neuron.update_weights(request).await.unwrap_err();
+
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    assert!(status.message().contains("expected 10"));
+    assert!(status.message().contains("received 11"));
+}
+
+#[test]
+fn test_input_schema_pads_and_truncates_extension() {
+    let schema = InputSchema::extended(2, 3, ExtensionPolicy::PadAndTruncate);
+
+    let padded = schema.assemble(vec![1.0, 2.0], vec![3.0]).unwrap();
+    assert_eq!(padded, vec![1.0, 2.0, 3.0, 0.0, 0.0]);
+
+    let truncated = schema.assemble(vec![1.0, 2.0], vec![3.0, 4.0, 5.0, 6.0]).unwrap();
+    assert_eq!(truncated, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+
+    let strict = InputSchema::extended(2, 3, ExtensionPolicy::Strict);
+    assert_eq!(
+        strict.assemble(vec![1.0, 2.0], vec![3.0]),
+        Err(InputSchemaError::ExtensionSize {
+            expected: 3,
+            received: 1
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_neuron_concurrent_updates_bump_version() {
+    let neuron_id = "test_neuron_concurrent".to_string();
+    let num_inputs = 10;
+    let activation = Box::new(ReLU);
+
+    let (_dir, db) = temp_db();
+    let neuron = Arc::new(
+        common::open_neuron(neuron_id, db, InputSchema::fixed(num_inputs), activation).unwrap(),
+    );
+
+    let mut handles = Vec::new();
+    for _ in 0..8 {
+        let neuron = neuron.clone();
+        handles.push(tokio::spawn(async move {
+            let weight_update = WeightUpdate {
+                deltas: vec![0.1; num_inputs],
+                bias_delta: 0.0,
+            };
+            neuron.update_weights(Request::new(weight_update)).await.unwrap();
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    let request = Request::new(ParametersRequest {});
+    let parameters = neuron.get_parameters(request).await.unwrap().into_inner();
+    assert_eq!(parameters.version, 8);
+
+    let input_signal = InputSignal {
+        values: vec![1.0; num_inputs],
+        request_id: String::new(),
+        timestamp_ms: 0,
+    };
+    let output_signal = neuron
+        .process_input(Request::new(input_signal))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(output_signal.weight_version, 8);
+}
+
+#[tokio::test]
+async fn test_neuron_restores_persisted_parameters() {
+    let neuron_id = "test_neuron_restore".to_string();
+    let num_inputs = 10;
+    let dir = TempDir::new().unwrap();
+
+    let (before, version) = {
+        let neuron = common::open_neuron(
+            neuron_id.clone(),
+            NeuronDb::new(dir.path()).unwrap(),
+            InputSchema::fixed(num_inputs),
+            Box::new(ReLU),
+        )
+        .unwrap();
+        let weight_update = WeightUpdate {
+            deltas: vec![0.1; num_inputs],
+            bias_delta: 0.25,
+        };
+        neuron.update_weights(Request::new(weight_update)).await.unwrap();
+        let request = Request::new(ParametersRequest {});
+        let parameters = neuron.get_parameters(request).await.unwrap().into_inner();
+        (parameters.weights, parameters.version)
+    };
+
+    let neuron = common::open_neuron(
+        neuron_id.clone(),
+        NeuronDb::new(dir.path()).unwrap(),
+        InputSchema::fixed(num_inputs),
+        Box::new(ReLU),
+    )
+    .unwrap();
+    let request = Request::new(ParametersRequest {});
+    let parameters = neuron.get_parameters(request).await.unwrap().into_inner();
+    assert_eq!(parameters.weights, before);
+    assert_eq!(parameters.version, version);
+    drop(neuron);
+
+    let result = common::open_neuron(
+        neuron_id,
+        NeuronDb::new(dir.path()).unwrap(),
+        InputSchema::fixed(num_inputs + 2),
+        Box::new(ReLU),
+    );
+    assert!(matches!(result, Err(NeuronError::DimensionMismatch { stored: 10, expected: 12 })));
+}
+
+#[tokio::test]
+async fn test_learning_state_is_persisted() {
+    let dir = TempDir::new().unwrap();
+    {
+        let neuron = common::open_neuron(
+            "test_learning_state".to_string(),
+            NeuronDb::new(dir.path()).unwrap(),
+            InputSchema::fixed(1),
+            Box::new(Sigmoid),
+        )
+        .unwrap()
+        .with_learning_rule(Box::new(Bcm::new(0.0, 1.0).unwrap()), 0.1)
+        .unwrap();
+        let input_signal = InputSignal {
+            values: vec![1.0],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        };
+        neuron.process_input(Request::new(input_signal)).await.unwrap();
+    }
+
+    // With a time constant of 1, theta is the square of the only output.
+    let db = NeuronDb::new(dir.path()).unwrap();
+    let state: LearningRuleState = serde_json::from_slice(&db.get(b"learning_state").unwrap().unwrap()).unwrap();
+    assert_eq!(state.name, "bcm");
+    assert_eq!(state.values.len(), 1);
+    assert!(state.values[0] > 0.0);
+}
+
+#[tokio::test]
+async fn test_neuron_backward() {
+    let neuron_id = "test_neuron_backward".to_string();
+    let num_inputs = 4;
+
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(
+        neuron_id,
+        db,
+        InputSchema::fixed(num_inputs),
+        Box::new(Sigmoid),
+    )
+    .unwrap();
+
+    let input_signal = InputSignal {
+        values: vec![1.0; num_inputs],
+        request_id: "req-1".to_string(),
+        timestamp_ms: 0,
+    };
+    let output_signal = neuron
+        .process_input(Request::new(input_signal))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let backward_request = BackwardRequest {
+        request_id: "req-1".to_string(),
+        upstream_gradient: 1.0,
+    };
+    let response = neuron
+        .backward(Request::new(backward_request))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.input_gradients.len(), num_inputs);
+    assert_eq!(response.weight_version, output_signal.weight_version + 1);
+
+    // The cached forward pass is consumed by the first Backward call.
+    let backward_request = BackwardRequest {
+        request_id: "req-1".to_string(),
+        upstream_gradient: 1.0,
+    };
+    let status = neuron.backward(Request::new(backward_request)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_neuron_process_batch() {
+    let num_inputs = 3;
+
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(
+        "test_neuron_batch".to_string(),
+        db,
+        InputSchema::fixed(num_inputs),
+        Box::new(Sigmoid),
+    )
+    .unwrap();
+
+    let inputs = (0..5)
+        .map(|i| InputSignal {
+            values: vec![i as f32; num_inputs],
+            request_id: format!("batch-{}", i),
+            timestamp_ms: 0,
+        })
+        .collect();
+    let batch = neuron
+        .process_batch(Request::new(InputBatch { inputs }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(batch.outputs.len(), 5);
+
+    // A single malformed input rejects the whole batch.
+    let inputs = vec![
+        InputSignal {
+            values: vec![1.0; num_inputs],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        },
+        InputSignal {
+            values: vec![1.0; num_inputs + 1],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        },
+    ];
+    let status = neuron
+        .process_batch(Request::new(InputBatch { inputs }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_rejected_batch_leaves_learning_untouched() {
+    let num_inputs = 3;
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(
+        "test_rejected_batch".to_string(),
+        db,
+        InputSchema::fixed(num_inputs),
+        Box::new(Sigmoid),
+    )
+    .unwrap()
+    .with_learning_rule(Box::new(Hebbian), 0.1)
+    .unwrap();
+    let before = neuron
+        .get_parameters(Request::new(ParametersRequest {}))
+        .await
+        .unwrap()
+        .into_inner();
+
+    // The malformed input comes last, after one the rule would learn from.
+    let inputs = vec![
+        InputSignal {
+            values: vec![1.0; num_inputs],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        },
+        InputSignal {
+            values: vec![1.0; num_inputs + 1],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        },
+    ];
+    let status = neuron
+        .process_batch(Request::new(InputBatch { inputs }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+    let after = neuron
+        .get_parameters(Request::new(ParametersRequest {}))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(after.weights, before.weights);
+    assert_eq!(after.version, before.version);
+}
+
+#[tokio::test]
+async fn test_neuron_backward_uses_forward_weights() {
+    let num_inputs = 3;
+    let (_dir, db) = temp_db();
+    let neuron = common::open_neuron(
+        "test_neuron_backward_weights".to_string(),
+        db,
+        InputSchema::fixed(num_inputs),
+        Box::new(Sigmoid),
+    )
+    .unwrap();
+
+    let forward_weights = neuron
+        .get_parameters(Request::new(ParametersRequest {}))
+        .await
+        .unwrap()
+        .into_inner()
+        .weights;
+    let input_signal = InputSignal {
+        values: vec![1.0; num_inputs],
+        request_id: "req-1".to_string(),
+        timestamp_ms: 0,
+    };
+    let output = neuron
+        .process_input(Request::new(input_signal))
+        .await
+        .unwrap()
+        .into_inner()
+        .value;
+
+    // An update between the forward pass and its Backward call must not
+    // change the gradients sent upstream.
+    let weight_update = WeightUpdate {
+        deltas: vec![0.5; num_inputs],
+        bias_delta: 0.0,
+    };
+    neuron.update_weights(Request::new(weight_update)).await.unwrap();
+
+    let backward_request = BackwardRequest {
+        request_id: "req-1".to_string(),
+        upstream_gradient: 1.0,
+    };
+    let response = neuron
+        .backward(Request::new(backward_request))
+        .await
+        .unwrap()
+        .into_inner();
+    let dz = output * (1.0 - output);
+    for (gradient, weight) in response.input_gradients.iter().zip(&forward_weights) {
+        assert!((gradient - dz * weight).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,37 @@
+// tests/optimizer_tests.rs
+use neurox::optimizer::{Optimizer, OptimizerConfig};
+
+#[test]
+fn test_sgd_momentum_accumulates() {
+    let config = OptimizerConfig::Sgd {
+        learning_rate: 0.5,
+        momentum: 0.5,
+    };
+    let mut optimizer = config.build();
+    assert_eq!(optimizer.deltas(&[1.0]), vec![-0.5]);
+    assert_eq!(optimizer.deltas(&[1.0]), vec![-0.75]);
+}
+
+#[test]
+fn test_optimizer_state_round_trip() {
+    let config: OptimizerConfig = serde_json::from_str(r#"{"type": "adam", "learning_rate": 0.01}"#).unwrap();
+    let mut optimizer = config.build();
+    optimizer.deltas(&[0.5, -0.5, 1.0]);
+    optimizer.deltas(&[0.25, 0.0, -1.0]);
+
+    let mut resumed = config.build();
+    resumed.load_state(optimizer.state());
+    assert_eq!(resumed.state(), optimizer.state());
+    assert_eq!(resumed.deltas(&[1.0, 1.0, 1.0]), optimizer.deltas(&[1.0, 1.0, 1.0]));
+}
+
+#[test]
+fn test_rms_prop_normalizes_step_size() {
+    let config = OptimizerConfig::RmsProp {
+        learning_rate: 0.1,
+        decay: 0.0,
+        epsilon: 0.0,
+    };
+    let mut optimizer = config.build();
+    assert_eq!(optimizer.deltas(&[4.0, -0.25]), vec![-0.1, 0.1]);
+}
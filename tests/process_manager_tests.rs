@@ -0,0 +1,244 @@
+// tests/process_manager_tests.rs
+use neurox::process_manager::{ProcessError, ProcessManager, RestartPolicy};
+use neurox::proto::supervisor_client::SupervisorClient;
+use neurox::proto::supervisor_server::SupervisorServer;
+use neurox::proto::SupervisorStatusRequest;
+use neurox::registry::NeuronRegistry;
+use neurox::supervisor::Supervisor;
+use neurox::topology::Topology;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic::Request;
+
+fn failing_topology(policy: &str) -> Topology {
+    Topology::parse(&format!(
+        r#"
+[[neurons]]
+id = "flaky"
+num_inputs = 1
+endpoint = "http://[::1]:50071"
+listen_addr = "[::1]:50071"
+command = ["sh", "-c", "exit 1"]
+restart = {{ policy = "{}", initial_backoff_ms = 10, max_backoff_ms = 40 }}
+"#,
+        policy
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_restart_config_defaults() {
+    let topology = Topology::parse(
+        r#"
+[[neurons]]
+id = "n1"
+num_inputs = 1
+endpoint = "http://[::1]:50072"
+"#,
+    )
+    .unwrap();
+    let restart = &topology.neurons[0].restart;
+    assert_eq!(restart.policy, RestartPolicy::OnFailure);
+    assert!(topology.neurons[0].command.is_empty());
+}
+
+#[tokio::test]
+async fn test_failed_process_is_restarted_with_backoff() {
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    let mut manager = ProcessManager::new();
+    let plan = manager.apply(failing_topology("on_failure")).unwrap();
+    assert_eq!(plan.start, vec!["flaky".to_string()]);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let now = Instant::now();
+    let events = manager.supervise(&registry, now);
+    assert_eq!(events.len(), 1);
+    assert!(events[0].1.contains("restarting"));
+    assert!(!manager.is_running("flaky"));
+
+    let events = manager.supervise(&registry, now + Duration::from_millis(20));
+    assert!(events[0].1.contains("restarted"));
+    assert!(manager.is_running("flaky"));
+}
+
+#[tokio::test]
+async fn test_never_policy_and_stop() {
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    let mut manager = ProcessManager::new();
+    manager.apply(failing_topology("never")).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let now = Instant::now();
+    let events = manager.supervise(&registry, now);
+    assert!(events[0].1.contains("not restarting"));
+    assert!(manager.supervise(&registry, now + Duration::from_secs(60)).is_empty());
+
+    manager.stop("flaky").unwrap();
+    assert!(manager.is_managed("flaky"));
+    assert!(matches!(manager.stop("ghost"), Err(ProcessError::NotManaged(_))));
+}
+
+#[tokio::test]
+async fn test_stop_all_stops_running_neurons_for_good() {
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    let mut manager = ProcessManager::new();
+    manager
+        .apply(
+            Topology::parse(
+                r#"
+[[neurons]]
+id = "sleeper"
+num_inputs = 1
+endpoint = "http://[::1]:50073"
+listen_addr = "[::1]:50073"
+command = ["sleep", "30"]
+restart = { policy = "always" }
+"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+    assert!(manager.is_running("sleeper"));
+
+    let stopped = manager.stop_all();
+    assert_eq!(stopped.len(), 1);
+    assert_eq!(stopped[0].0, "sleeper");
+    assert!(!manager.is_running("sleeper"));
+    assert!(manager.supervise(&registry, Instant::now() + Duration::from_secs(60)).is_empty());
+    assert!(manager.stop_all().is_empty());
+}
+
+async fn neuron_status(client: &mut SupervisorClient<tonic::transport::Channel>, neuron_id: &str) -> Option<String> {
+    client
+        .get_neuron_status(Request::new(SupervisorStatusRequest {}))
+        .await
+        .unwrap()
+        .into_inner()
+        .neuron_status
+        .remove(neuron_id)
+}
+
+#[tokio::test]
+async fn test_stopped_task_stops_heartbeating() {
+    let dir = tempfile::tempdir().unwrap();
+    // Bound before serving, so that clients can connect right away.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let supervisor_endpoint = format!("http://{}", listener.local_addr().unwrap());
+
+    let lease = Duration::from_millis(600);
+    let (telegram_bot_sender, _telegram_bot_receiver) = mpsc::channel(10);
+    let supervisor = Supervisor::new(telegram_bot_sender).with_lease(lease);
+    supervisor.watch_leases();
+    tokio::spawn(
+        Server::builder()
+            .add_service(SupervisorServer::new(supervisor))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    let mut manager = ProcessManager::new()
+        .with_supervisor_endpoint(supervisor_endpoint.clone())
+        .with_data_dir(dir.path());
+    manager
+        .apply(
+            Topology::parse(
+                r#"
+[[neurons]]
+id = "task"
+num_inputs = 1
+endpoint = "http://127.0.0.1:50092"
+listen_addr = "127.0.0.1:0"
+"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+    let mut client = SupervisorClient::connect(supervisor_endpoint).await.unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while neuron_status(&mut client, "task").await.is_none() {
+        assert!(Instant::now() < deadline, "Neuron never registered");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // The stop completes once the neuron has deregistered.
+    for (_, stopping) in manager.stop_all() {
+        stopping.await.unwrap();
+    }
+    assert_eq!(neuron_status(&mut client, "task").await, None);
+    // Heartbeats would register the neuron again within a lease.
+    tokio::time::sleep(lease).await;
+    assert_eq!(neuron_status(&mut client, "task").await, None);
+}
+
+#[tokio::test]
+async fn test_stop_all_lets_children_exit_on_sigterm() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("terminated");
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    let mut manager = ProcessManager::new().with_stop_deadline(Duration::from_secs(5));
+    manager
+        .apply(
+            Topology::parse(&format!(
+                r#"
+[[neurons]]
+id = "graceful"
+num_inputs = 1
+endpoint = "http://[::1]:50074"
+listen_addr = "[::1]:50074"
+command = ["sh", "-c", "trap 'touch {}; exit 0' TERM; while true; do sleep 0.1; done"]
+"#,
+                marker.display()
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+    // Give the shell time to install its trap.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let started = Instant::now();
+    for (_, stopping) in manager.stop_all() {
+        stopping.await.unwrap();
+    }
+    assert!(marker.exists());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(manager.supervise(&registry, Instant::now()).is_empty());
+}
+
+#[tokio::test]
+async fn test_readded_neuron_waits_for_its_old_instance() {
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    let mut manager = ProcessManager::new().with_stop_deadline(Duration::from_secs(5));
+    let topology = Topology::parse(
+        r#"
+[[neurons]]
+id = "lingering"
+num_inputs = 1
+endpoint = "http://[::1]:50075"
+listen_addr = "[::1]:50075"
+command = ["sh", "-c", "trap 'sleep 1; exit 0' TERM; while true; do sleep 0.1; done"]
+"#,
+    )
+    .unwrap();
+    manager.apply(topology.clone()).unwrap();
+    // Give the shell time to install its trap.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    manager.apply(Topology::default()).unwrap();
+    let plan = manager.apply(topology).unwrap();
+    assert_eq!(plan.start, vec!["lingering".to_string()]);
+    assert!(manager.is_managed("lingering"));
+    assert!(!manager.is_running("lingering"));
+    assert!(manager.supervise(&registry, Instant::now()).is_empty());
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let events = manager.supervise(&registry, Instant::now());
+    assert!(events[0].1.contains("restarted"));
+    assert!(manager.is_running("lingering"));
+
+    for (_, stopping) in manager.stop_all() {
+        stopping.await.unwrap();
+    }
+}
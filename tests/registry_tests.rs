@@ -0,0 +1,59 @@
+// tests/registry_tests.rs
+use neurox::registry::{NeuronInfo, NeuronRegistry};
+use std::time::{Duration, Instant};
+
+fn info(neuron_id: &str) -> NeuronInfo {
+    NeuronInfo {
+        neuron_id: neuron_id.to_string(),
+        address: "http://[::1]:50051".to_string(),
+        num_inputs: 10,
+        activation: "relu".to_string(),
+        extensions: vec!["eye".to_string()],
+    }
+}
+
+#[test]
+fn test_registry_expires_silent_neurons_once() {
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    let start = Instant::now();
+    registry.register(info("n1"), start);
+    registry.register(info("n2"), start);
+
+    assert!(registry.expire(start + Duration::from_secs(5)).is_empty());
+    assert!(registry.heartbeat("n1", start + Duration::from_secs(8)));
+
+    let expired = registry.expire(start + Duration::from_secs(12));
+    assert_eq!(expired, vec!["n2".to_string()]);
+    assert!(!registry.is_alive("n2"));
+    assert!(registry.is_alive("n1"));
+
+    // An expired neuron is only reported once.
+    assert!(registry.expire(start + Duration::from_secs(13)).is_empty());
+
+    // A heartbeat brings it back.
+    assert!(registry.heartbeat("n2", start + Duration::from_secs(14)));
+    assert!(registry.is_alive("n2"));
+}
+
+#[test]
+fn test_registry_rejects_heartbeat_from_unknown_neuron() {
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    assert!(!registry.heartbeat("ghost", Instant::now()));
+
+    registry.register(info("n1"), Instant::now());
+    assert_eq!(registry.get("n1"), Some(info("n1")));
+    assert_eq!(registry.neurons().len(), 1);
+}
+
+#[test]
+fn test_registry_forgets_deregistered_neuron() {
+    let registry = NeuronRegistry::new(Duration::from_secs(10));
+    let start = Instant::now();
+    registry.register(info("n1"), start);
+
+    assert!(registry.deregister("n1"));
+    assert!(!registry.deregister("n1"));
+    assert!(registry.get("n1").is_none());
+    assert!(!registry.heartbeat("n1", start + Duration::from_secs(1)));
+    assert!(registry.expire(start + Duration::from_secs(20)).is_empty());
+}
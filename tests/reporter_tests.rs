@@ -0,0 +1,55 @@
+// tests/reporter_tests.rs
+mod common;
+
+use neurox::activation::ReLU;
+use neurox::input_schema::InputSchema;
+use neurox::proto::InputSignal;
+use neurox::reporter::Reporter;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tonic::Request;
+
+#[test]
+fn test_reporter_coalesces_status_and_bounds_metrics() {
+    // Outside a runtime the background task never starts, so the buffer can
+    // be inspected directly.
+    let reporter = Reporter::new("http://127.0.0.1:1".to_string());
+    for i in 0..10 {
+        reporter.report_status("n1", format!("status {}", i));
+    }
+    reporter.report_status("n2", "Idle".to_string());
+    for i in 0..1000 {
+        let mut metrics = HashMap::new();
+        metrics.insert("processing_time".to_string(), i as f64);
+        reporter.report_metrics("n1", metrics);
+    }
+
+    let (status, metrics) = reporter.pending();
+    assert_eq!(status, 2);
+    assert_eq!(metrics, 256);
+    assert_eq!(reporter.dropped_metrics(), 1000 - 256);
+}
+
+#[tokio::test]
+async fn test_neuron_serves_without_supervisor() {
+    let (_dir, db) = common::temp_db();
+    let neuron = Arc::new(
+        common::open_neuron(
+            "test_neuron_no_supervisor".to_string(),
+            db,
+            InputSchema::fixed(2),
+            Box::new(ReLU),
+        )
+        .unwrap()
+        .with_reporter(Reporter::new("http://127.0.0.1:1".to_string())),
+    );
+
+    for _ in 0..3 {
+        let input_signal = InputSignal {
+            values: vec![1.0, 1.0],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        };
+        neuron.process_input(Request::new(input_signal)).await.unwrap();
+    }
+}
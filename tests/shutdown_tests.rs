@@ -0,0 +1,82 @@
+// tests/shutdown_tests.rs
+mod common;
+
+use neurox::activation::ReLU;
+use neurox::input_schema::InputSchema;
+use neurox::proto::{InputSignal, WeightUpdate};
+use neurox::reporter::Reporter;
+use neurox::shutdown::{self, Drain};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tonic::{Code, Request};
+
+#[tokio::test]
+async fn test_drain_waits_for_admitted_work() {
+    let drain = Arc::new(Drain::new());
+    let guard = Drain::enter(&drain).unwrap();
+    drain.close();
+    assert!(Drain::enter(&drain).is_none());
+    assert_eq!(drain.in_flight(), 1);
+
+    let waiting = drain.clone();
+    let drained = tokio::spawn(async move { waiting.drained().await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!drained.is_finished());
+
+    drop(guard);
+    tokio::time::timeout(Duration::from_secs(1), drained).await.unwrap().unwrap();
+    assert_eq!(drain.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_wait_completes_on_shutdown() {
+    let (sender, receiver) = watch::channel(false);
+    let waiting = tokio::spawn(shutdown::wait(receiver));
+    sender.send(true).unwrap();
+    tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_neuron_rejects_inputs_and_updates_once_shutting_down() {
+    let (_dir, db) = common::temp_db();
+    let neuron = Arc::new(
+        common::open_neuron(
+            "test_neuron_shutdown".to_string(),
+            db,
+            InputSchema::fixed(2),
+            Box::new(ReLU),
+        )
+        .unwrap()
+        .with_reporter(Reporter::new("http://127.0.0.1:1".to_string())),
+    );
+    let input = || InputSignal {
+        values: vec![1.0, 1.0],
+        request_id: String::new(),
+        timestamp_ms: 0,
+    };
+    neuron.process_input(Request::new(input())).await.unwrap();
+    assert!(neuron.is_ready());
+
+    // The supervisor is unreachable, so the final report never completes.
+    let shutdown = tokio::time::timeout(Duration::from_millis(200), neuron.shutdown()).await;
+    assert!(shutdown.is_err());
+
+    assert!(!neuron.is_ready());
+    let status = neuron.process_input(Request::new(input())).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    let status = neuron
+        .update_weights(Request::new(WeightUpdate {
+            deltas: vec![0.1, 0.1],
+            bias_delta: 0.0,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+}
+
+#[tokio::test]
+async fn test_reporter_flush_without_reports() {
+    let reporter = Reporter::new("http://127.0.0.1:1".to_string());
+    tokio::time::timeout(Duration::from_secs(1), reporter.flush()).await.unwrap();
+}
@@ -0,0 +1,69 @@
+// tests/spiking_tests.rs
+use neurox::spiking::{LifConfig, MembraneState, Spiking};
+
+#[test]
+fn test_membrane_leaks_towards_rest() {
+    let config = LifConfig {
+        tau_ms: 10.0,
+        threshold: 10.0,
+        ..LifConfig::default()
+    };
+    let mut membrane = MembraneState::resting(&config);
+    assert!(!membrane.integrate(&config, 1.0, 0));
+    assert_eq!(membrane.potential, 1.0);
+
+    assert!(!membrane.integrate(&config, 0.0, 10));
+    assert!((membrane.potential - (-1.0f32).exp()).abs() < 1e-6);
+}
+
+#[test]
+fn test_membrane_fires_resets_and_respects_refractory_period() {
+    let config = LifConfig {
+        threshold: 1.0,
+        reset_potential: -0.5,
+        refractory_ms: 5,
+        ..LifConfig::default()
+    };
+    let mut membrane = MembraneState::resting(&config);
+    assert!(membrane.integrate(&config, 1.5, 100));
+    assert_eq!(membrane.potential, -0.5);
+    assert_eq!(membrane.last_spike_ms, Some(100));
+
+    // Input during the refractory period is ignored.
+    assert!(!membrane.integrate(&config, 5.0, 102));
+    assert!(membrane.potential < 0.0);
+
+    assert!(membrane.integrate(&config, 5.0, 105));
+}
+
+#[tokio::test]
+async fn test_spiking_broadcasts_spikes() {
+    let config = LifConfig::default();
+    let spiking = Spiking::new(config.clone(), MembraneState::resting(&config));
+    let mut spikes = spiking.subscribe();
+
+    let mut persisted = None;
+    let (_, spiked) = spiking
+        .integrate(2.0, 42, 7, 1.0, |membrane| {
+            persisted = Some(membrane.clone());
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+    assert!(spiked);
+    assert!(persisted.is_some());
+
+    let spike = spikes.recv().await.unwrap();
+    assert_eq!(spike.timestamp_ms, 42);
+    assert_eq!(spike.weight_version, 7);
+}
+
+#[tokio::test]
+async fn test_spiking_does_not_broadcast_unpersisted_spikes() {
+    let config = LifConfig::default();
+    let spiking = Spiking::new(config.clone(), MembraneState::resting(&config));
+    let mut spikes = spiking.subscribe();
+
+    let result = spiking.integrate(2.0, 42, 7, 1.0, |_| Err("disk full"));
+    assert_eq!(result.unwrap_err(), "disk full");
+    assert!(spikes.try_recv().is_err());
+}
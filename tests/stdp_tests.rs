@@ -0,0 +1,135 @@
+// tests/stdp_tests.rs
+mod common;
+
+use neurox::activation::ReLU;
+use neurox::input_schema::InputSchema;
+use neurox::neuromodulation::{ModulationBinding, ModulationTarget};
+use neurox::proto::{InputSignal, ParametersRequest, WeightUpdate};
+use neurox::reporter::Reporter;
+use neurox::spiking::LifConfig;
+use neurox::stdp::{StdpConfig, StdpTraces};
+use std::collections::HashMap;
+use tokio::sync::watch;
+use tonic::Request;
+
+#[test]
+fn test_pre_before_post_potentiates() {
+    let config = StdpConfig::default();
+    let mut traces = StdpTraces::default();
+    let weights = vec![0.5, 0.5];
+
+    // Input on synapse 0 only, no spike yet.
+    let deltas = traces.update(&config, &[1.0, 0.0], false, 0, &weights, 1.0);
+    assert_eq!(deltas, vec![0.0, 0.0]);
+
+    // Post-synaptic spike 5ms later potentiates synapse 0 only.
+    let deltas = traces.update(&config, &[0.0, 0.0], true, 5, &weights, 1.0);
+    assert!(deltas[0] > 0.0);
+    assert_eq!(deltas[1], 0.0);
+}
+
+#[test]
+fn test_post_before_pre_depresses() {
+    let config = StdpConfig::default();
+    let mut traces = StdpTraces::default();
+    let weights = vec![0.5];
+
+    traces.update(&config, &[0.0], true, 0, &weights, 1.0);
+    let deltas = traces.update(&config, &[1.0], false, 5, &weights, 1.0);
+    assert!(deltas[0] < 0.0);
+}
+
+#[test]
+fn test_weights_stay_within_bounds() {
+    let config = StdpConfig {
+        a_plus: 1.0,
+        w_max: 1.0,
+        ..StdpConfig::default()
+    };
+    let mut traces = StdpTraces::default();
+    let weights = vec![0.9];
+
+    let deltas = traces.update(&config, &[1.0], true, 0, &weights, 1.0);
+    assert!((weights[0] + deltas[0] - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_weights_outside_bounds_are_not_pulled_in() {
+    let config = StdpConfig::default();
+    let mut traces = StdpTraces::default();
+    // Xavier initialization yields negative weights below the default w_min.
+    let weights = vec![-0.3];
+
+    let deltas = traces.update(&config, &[1.0], false, 0, &weights, 1.0);
+    assert_eq!(deltas, vec![0.0]);
+
+    // Depression cannot push the weight further below w_min...
+    traces.update(&config, &[0.0], true, 10, &weights, 1.0);
+    let deltas = traces.update(&config, &[1.0], false, 15, &weights, 1.0);
+    assert_eq!(deltas, vec![0.0]);
+
+    // ...but potentiation still moves it towards the range.
+    let deltas = traces.update(&config, &[0.0], true, 20, &weights, 1.0);
+    assert!(deltas[0] > 0.0);
+}
+
+#[tokio::test]
+async fn test_modulated_stdp_keeps_weights_within_bounds() {
+    let (_dir, db) = common::temp_db();
+    let mut levels = HashMap::new();
+    levels.insert("dopamine".to_string(), 10.0);
+    let (_levels_sender, levels) = watch::channel(levels);
+    let config = StdpConfig {
+        a_plus: 0.5,
+        ..StdpConfig::default()
+    };
+    let neuron = common::open_neuron("test_neuron_stdp".to_string(), db, InputSchema::fixed(2), Box::new(ReLU))
+        .unwrap()
+        .with_reporter(Reporter::new("http://127.0.0.1:1".to_string()))
+        .with_spiking(LifConfig::default())
+        .unwrap()
+        .with_stdp(config.clone())
+        .unwrap()
+        .with_neuromodulation(
+            vec![ModulationBinding {
+                modulator: "dopamine".to_string(),
+                target: ModulationTarget::LearningRate,
+                baseline: 0.0,
+                sensitivity: 1.0,
+            }],
+            levels,
+        );
+
+    // Start inside the bounds, close enough to w_max that an unclipped,
+    // elevenfold potentiation would overshoot it.
+    let weights = neuron
+        .get_parameters(Request::new(ParametersRequest {}))
+        .await
+        .unwrap()
+        .into_inner()
+        .weights;
+    let deltas = weights.iter().map(|weight| 0.9 - weight).collect();
+    neuron
+        .update_weights(Request::new(WeightUpdate { deltas, bias_delta: 0.0 }))
+        .await
+        .unwrap();
+
+    for i in 0..5 {
+        let input = InputSignal {
+            values: vec![5.0, 5.0],
+            request_id: String::new(),
+            timestamp_ms: (i + 1) * 10,
+        };
+        neuron.process_input(Request::new(input)).await.unwrap();
+    }
+
+    let weights = neuron
+        .get_parameters(Request::new(ParametersRequest {}))
+        .await
+        .unwrap()
+        .into_inner()
+        .weights;
+    for weight in weights {
+        assert!(weight >= config.w_min && weight <= config.w_max + 1e-6, "weight {} out of bounds", weight);
+    }
+}
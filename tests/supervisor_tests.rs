@@ -0,0 +1,104 @@
+// tests/supervisor_tests.rs
+use neurox::proto::supervisor_server::Supervisor as SupervisorTrait;
+use neurox::proto::{
+    NeuromodulatorSubscription, NeuromodulatorUpdate, SupervisorMetricsRequest, SupervisorRequest, SupervisorStatusRequest,
+};
+use neurox::supervisor::Supervisor;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+use tonic::{Code, Request};
+
+fn report(neuron_id: &str, status: &str, metrics: &str, command: &str, args: Vec<String>) -> Request<SupervisorRequest> {
+    Request::new(SupervisorRequest {
+        neuron_id: neuron_id.to_string(),
+        status: status.to_string(),
+        metrics: metrics.to_string(),
+        command: command.to_string(),
+        args,
+    })
+}
+
+#[tokio::test]
+async fn test_supervisor_records_status_and_metrics() {
+    let (telegram_bot_sender, _telegram_bot_receiver) = mpsc::channel(10);
+    let supervisor = Supervisor::new(telegram_bot_sender);
+
+    supervisor
+        .report_neuron_status(report("n1", "Idle", "", "", vec![]))
+        .await
+        .unwrap();
+    supervisor
+        .report_neuron_metrics(report("n1", "", r#"{"processing_time": 0.5}"#, "", vec![]))
+        .await
+        .unwrap();
+
+    let status = supervisor
+        .get_neuron_status(Request::new(SupervisorStatusRequest {}))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(status.neuron_status.get("n1").map(String::as_str), Some("Idle"));
+
+    let metrics = supervisor
+        .get_neuron_metrics(Request::new(SupervisorMetricsRequest {
+            neuron_id: "n1".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(metrics.metrics.get("processing_time"), Some(&0.5));
+
+    let status = supervisor
+        .report_neuron_metrics(report("n1", "", "not json", "", vec![]))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_telegram_command_reads_shared_state() {
+    let (telegram_bot_sender, mut telegram_bot_receiver) = mpsc::channel(10);
+    let supervisor = Supervisor::new(telegram_bot_sender);
+
+    supervisor
+        .report_neuron_status(report("n1", "Processing input", "", "", vec![]))
+        .await
+        .unwrap();
+    supervisor
+        .process_telegram_command(report("", "", "", "/neuron_status", vec![]))
+        .await
+        .unwrap();
+
+    let (command, response) = telegram_bot_receiver.recv().await.unwrap();
+    assert_eq!(command, "/neuron_status");
+    assert_eq!(response, "Neuron n1: Processing input");
+}
+
+#[tokio::test]
+async fn test_new_subscriber_receives_levels_set_before_subscribing() {
+    let (telegram_bot_sender, _telegram_bot_receiver) = mpsc::channel(10);
+    let supervisor = Supervisor::new(telegram_bot_sender);
+
+    supervisor
+        .set_neuromodulator(Request::new(NeuromodulatorUpdate {
+            name: "dopamine".to_string(),
+            level: 0.7,
+        }))
+        .await
+        .unwrap();
+
+    let mut levels = supervisor
+        .subscribe_neuromodulators(Request::new(NeuromodulatorSubscription {
+            neuron_id: "n1".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    let first = tokio::time::timeout(Duration::from_secs(1), levels.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.levels.get("dopamine"), Some(&0.7));
+}
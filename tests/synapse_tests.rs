@@ -0,0 +1,243 @@
+// tests/synapse_tests.rs
+mod common;
+
+use neurox::activation::ReLU;
+use neurox::database::NeuronDb;
+use neurox::input_schema::InputSchema;
+use neurox::neuron::{Neuron, SharedNeuron};
+use neurox::proto::neuron_service_server::NeuronServiceServer;
+use neurox::proto::{InputSignal, SynapticInput};
+use neurox::reporter::Reporter;
+use neurox::spiking::LifConfig;
+use neurox::synapse::{DownstreamSynapse, SynapseConfig, SynapseError, Synapses, UpstreamSynapse};
+use neurox::telemetry::REQUEST_ID_HEADER;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tonic::transport::Server;
+use tonic::{Code, Request};
+
+fn upstream(source_id: &str, slot: usize) -> UpstreamSynapse {
+    UpstreamSynapse {
+        source_id: source_id.to_string(),
+        slot,
+    }
+}
+
+#[test]
+fn test_wave_fires_after_all_upstream_inputs() {
+    let config = SynapseConfig {
+        upstream: vec![upstream("a", 0), upstream("b", 2)],
+        downstream: vec![],
+    };
+    let synapses = Synapses::connect(config, 3).unwrap();
+
+    assert_eq!(synapses.receive("wave-1", "a", 0, 0.5).unwrap(), None);
+    // A different wave does not complete wave-1.
+    assert_eq!(synapses.receive("wave-2", "b", 2, 1.0).unwrap(), None);
+    assert_eq!(
+        synapses.receive("wave-1", "b", 2, 0.25).unwrap(),
+        Some(vec![0.5, 0.0, 0.25])
+    );
+}
+
+#[test]
+fn test_rejects_unknown_source_and_wrong_slot() {
+    let config = SynapseConfig {
+        upstream: vec![upstream("a", 0)],
+        downstream: vec![],
+    };
+    let synapses = Synapses::connect(config, 2).unwrap();
+
+    assert!(matches!(
+        synapses.receive("wave-1", "z", 0, 1.0),
+        Err(SynapseError::UnknownSource(_))
+    ));
+    assert!(matches!(
+        synapses.receive("wave-1", "a", 1, 1.0),
+        Err(SynapseError::SlotMismatch { expected: 0, received: 1, .. })
+    ));
+}
+
+#[test]
+fn test_rejects_invalid_topology() {
+    let out_of_range = SynapseConfig {
+        upstream: vec![upstream("a", 4)],
+        downstream: vec![],
+    };
+    assert!(matches!(
+        Synapses::connect(out_of_range, 2),
+        Err(SynapseError::SlotOutOfRange { slot: 4, size: 2 })
+    ));
+
+    let duplicate = SynapseConfig {
+        upstream: vec![upstream("a", 0), upstream("b", 0)],
+        downstream: vec![],
+    };
+    assert!(matches!(
+        Synapses::connect(duplicate, 2),
+        Err(SynapseError::DuplicateSlot(0))
+    ));
+
+    let duplicate_source = SynapseConfig {
+        upstream: vec![upstream("a", 0), upstream("a", 1)],
+        downstream: vec![],
+    };
+    assert!(matches!(
+        Synapses::connect(duplicate_source, 2),
+        Err(SynapseError::DuplicateSource(source_id)) if source_id == "a"
+    ));
+}
+
+#[test]
+fn test_rejects_missing_wave_id() {
+    let config = SynapseConfig {
+        upstream: vec![upstream("a", 0)],
+        downstream: vec![],
+    };
+    let synapses = Synapses::connect(config, 1).unwrap();
+
+    assert!(matches!(
+        synapses.receive("", "a", 0, 1.0),
+        Err(SynapseError::MissingWaveId(_))
+    ));
+}
+
+fn open_neuron(id: &str, dir: &TempDir, num_inputs: usize, synapses: SynapseConfig) -> Neuron {
+    common::open_neuron(
+        id.to_string(),
+        NeuronDb::new(dir.path().join(id)).unwrap(),
+        InputSchema::fixed(num_inputs),
+        Box::new(ReLU),
+    )
+    .unwrap()
+    .with_reporter(Reporter::new("http://127.0.0.1:1".to_string()))
+    .with_synapses(synapses)
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_upstream_neurons_without_request_id_share_a_wave() {
+    let dir = TempDir::new().unwrap();
+    let downstream = |slot| SynapseConfig {
+        upstream: vec![],
+        downstream: vec![DownstreamSynapse {
+            target_id: "c".to_string(),
+            endpoint: "http://127.0.0.1:50081".to_string(),
+            slot,
+        }],
+    };
+    let a = open_neuron("a", &dir, 1, downstream(0));
+    let b = open_neuron("b", &dir, 1, downstream(1));
+    let c = Arc::new(open_neuron(
+        "c",
+        &dir,
+        2,
+        SynapseConfig {
+            upstream: vec![upstream("a", 0), upstream("b", 1)],
+            downstream: vec![],
+        },
+    ));
+    tokio::spawn(
+        Server::builder()
+            .add_service(NeuronServiceServer::new(SharedNeuron::new(c.clone())))
+            .serve("127.0.0.1:50081".parse().unwrap()),
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // The client feeding the network sends one request id with every input.
+    for neuron in &[&a, &b] {
+        let mut request = Request::new(InputSignal {
+            values: vec![1.0],
+            request_id: String::new(),
+            timestamp_ms: 0,
+        });
+        request
+            .metadata_mut()
+            .insert(REQUEST_ID_HEADER, "input-1".parse().unwrap());
+        neuron.process_input(request).await.unwrap();
+    }
+
+    let mut fired = false;
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        if c.metrics_snapshot().metrics.get("request_count") == Some(&1.0) {
+            fired = true;
+            break;
+        }
+    }
+    assert!(fired, "Neuron c never fired for the shared wave");
+
+    let status = c
+        .receive_synaptic_input(Request::new(SynapticInput {
+            source_id: "a".to_string(),
+            slot: 0,
+            value: 1.0,
+            wave_id: String::new(),
+            timestamp_ms: 0,
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_silent_spiking_upstream_completes_the_wave() {
+    let dir = TempDir::new().unwrap();
+    let downstream = |slot| SynapseConfig {
+        upstream: vec![],
+        downstream: vec![DownstreamSynapse {
+            target_id: "c".to_string(),
+            endpoint: "http://127.0.0.1:50082".to_string(),
+            slot,
+        }],
+    };
+    let a = open_neuron("a", &dir, 1, downstream(0));
+    // A threshold no input reaches, so that s never spikes.
+    let s = open_neuron("s", &dir, 1, downstream(1))
+        .with_spiking(LifConfig {
+            threshold: 1000.0,
+            ..LifConfig::default()
+        })
+        .unwrap();
+    let c = Arc::new(open_neuron(
+        "c",
+        &dir,
+        2,
+        SynapseConfig {
+            upstream: vec![upstream("a", 0), upstream("s", 1)],
+            downstream: vec![],
+        },
+    ));
+    tokio::spawn(
+        Server::builder()
+            .add_service(NeuronServiceServer::new(SharedNeuron::new(c.clone())))
+            .serve("127.0.0.1:50082".parse().unwrap()),
+    );
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    for neuron in &[&a, &s] {
+        let output = neuron
+            .process_input(Request::new(InputSignal {
+                values: vec![1.0],
+                request_id: "wave-1".to_string(),
+                timestamp_ms: 0,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        if neuron.id() == "s" {
+            assert_eq!(output.value, 0.0);
+        }
+    }
+
+    let mut fired = false;
+    for _ in 0..20 {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        if c.metrics_snapshot().metrics.get("request_count") == Some(&1.0) {
+            fired = true;
+            break;
+        }
+    }
+    assert!(fired, "Neuron c never fired for a wave with a silent spiking input");
+}
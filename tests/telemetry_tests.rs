@@ -0,0 +1,63 @@
+// tests/telemetry_tests.rs
+use neurox::extensions::EyeExt;
+use neurox::neuron_state::{ExtensionTokens, NeuronState, Parameters};
+use neurox::proto::eye_ext_server::EyeExt as EyeExtTrait;
+use neurox::proto::EyeExtRequest;
+use neurox::telemetry::{self, REQUEST_ID_HEADER};
+use tokio::sync::mpsc;
+use tonic::Request;
+
+fn header<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+#[tokio::test]
+async fn test_request_id_propagates_to_outgoing_requests() {
+    let mut incoming = Request::new(());
+    incoming
+        .metadata_mut()
+        .insert(REQUEST_ID_HEADER, "input-42".parse().unwrap());
+
+    let context = telemetry::server_context("process_input", &incoming);
+    assert_eq!(context.request_id(), "input-42");
+    let outgoing = context
+        .run(async {
+            assert_eq!(telemetry::current_request_id().as_deref(), Some("input-42"));
+            telemetry::traced_request(())
+        })
+        .await;
+    assert_eq!(header(&outgoing).as_deref(), Some("input-42"));
+    assert_eq!(telemetry::current_request_id(), None);
+}
+
+#[tokio::test]
+async fn test_new_request_id_without_caller() {
+    let context = telemetry::server_context("process_input", &Request::new(()));
+    assert_eq!(context.request_id().len(), 16);
+
+    let first = header(&telemetry::traced_request(())).unwrap();
+    let second = header(&telemetry::traced_request(())).unwrap();
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn test_extension_tokens_reach_the_neuron_with_their_request_id() {
+    let (extension_sender, extension_receiver) = mpsc::channel(8);
+    let eye_ext = EyeExt::new(extension_sender);
+    let state = NeuronState::new(Parameters::new(vec![0.0; 4], 0.0), Some(extension_receiver));
+
+    let mut request = Request::new(EyeExtRequest { image_data: vec![1, 2, 3] });
+    request
+        .metadata_mut()
+        .insert(REQUEST_ID_HEADER, "image-7".parse().unwrap());
+    eye_ext.process_image(request).await.unwrap();
+
+    let drained = state.drain_extension_tokens().await;
+    assert_eq!(drained.len(), 1);
+    assert_eq!(drained[0].request_id, "image-7");
+    assert_eq!(state.drain_extension_tokens().await, Vec::<ExtensionTokens>::new());
+}
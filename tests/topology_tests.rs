@@ -0,0 +1,124 @@
+// tests/topology_tests.rs
+use neurox::topology::{ReconcilePlan, Topology, TopologyError};
+use std::collections::HashSet;
+
+const NETWORK: &str = r#"
+[[neurons]]
+id = "a"
+num_inputs = 2
+endpoint = "http://[::1]:50061"
+listen_addr = "[::1]:50061"
+
+[[neurons]]
+id = "b"
+num_inputs = 2
+endpoint = "http://[::1]:50062"
+
+[[neurons]]
+id = "c"
+num_inputs = 2
+activation = "sigmoid"
+endpoint = "http://[::1]:50063"
+listen_addr = "[::1]:50063"
+
+[[connections]]
+from = "a"
+to = "c"
+slot = 0
+
+[[connections]]
+from = "b"
+to = "c"
+slot = 1
+"#;
+
+#[test]
+fn test_parse_and_derive_synapses() {
+    let topology = Topology::parse(NETWORK).unwrap();
+    assert_eq!(topology.neurons.len(), 3);
+
+    let c = topology.synapse_config("c");
+    assert_eq!(c.upstream.len(), 2);
+    assert!(c.downstream.is_empty());
+
+    let a = topology.synapse_config("a");
+    assert_eq!(a.downstream[0].target_id, "c");
+    assert_eq!(a.downstream[0].endpoint, "http://[::1]:50063");
+    assert_eq!(a.downstream[0].slot, 0);
+}
+
+#[test]
+fn test_rejects_dangling_reference() {
+    let network = format!("{}\n[[connections]]\nfrom = \"c\"\nto = \"x\"\nslot = 0\n", NETWORK);
+    assert!(matches!(
+        Topology::parse(&network),
+        Err(TopologyError::DanglingReference { .. })
+    ));
+}
+
+#[test]
+fn test_rejects_slot_out_of_range() {
+    let network = format!("{}\n[[connections]]\nfrom = \"a\"\nto = \"b\"\nslot = 2\n", NETWORK);
+    assert!(matches!(
+        Topology::parse(&network),
+        Err(TopologyError::SlotOutOfRange { slot: 2, num_inputs: 2, .. })
+    ));
+}
+
+#[test]
+fn test_rejects_partly_fed_neuron() {
+    let network = NETWORK.replace("id = \"c\"\nnum_inputs = 2", "id = \"c\"\nnum_inputs = 3");
+    assert!(matches!(
+        Topology::parse(&network),
+        Err(TopologyError::UnfedInputs { num_inputs: 3, connected: 2, .. })
+    ));
+}
+
+#[test]
+fn test_rejects_cycle() {
+    let network = format!("{}\n[[connections]]\nfrom = \"c\"\nto = \"a\"\nslot = 1\n", NETWORK);
+    assert!(matches!(Topology::parse(&network), Err(TopologyError::Cycle(_))));
+}
+
+#[test]
+fn test_reconcile_plan() {
+    let topology = Topology::parse(NETWORK).unwrap();
+    let running: HashSet<String> = vec!["a".to_string(), "old".to_string()].into_iter().collect();
+    let plan = ReconcilePlan::new(&topology, None, &running);
+    assert_eq!(plan.start, vec!["c".to_string()]);
+    assert_eq!(plan.stop, vec!["old".to_string()]);
+    assert!(plan.restart.is_empty());
+}
+
+#[test]
+fn test_reconcile_plan_restarts_changed_neurons() {
+    let previous = Topology::parse(NETWORK).unwrap();
+    let running: HashSet<String> = vec!["a".to_string(), "c".to_string()].into_iter().collect();
+
+    // Unchanged, or only reordered or with new restart settings: nothing to do.
+    let mut same = previous.clone();
+    same.connections.reverse();
+    same.neurons[0].restart.max_backoff_ms = 1_000;
+    assert_eq!(
+        ReconcilePlan::new(&same, Some(&previous), &running),
+        ReconcilePlan::default()
+    );
+
+    let mut changed = previous.clone();
+    changed.neurons[2].activation = "relu".to_string();
+    let plan = ReconcilePlan::new(&changed, Some(&previous), &running);
+    assert_eq!(plan.restart, vec!["c".to_string()]);
+
+    // Moving a connection restarts both ends of it.
+    let mut rewired = previous.clone();
+    rewired.connections[0].slot = 1;
+    rewired.connections[1].slot = 0;
+    let plan = ReconcilePlan::new(&rewired, Some(&previous), &running);
+    assert_eq!(plan.restart, vec!["a".to_string(), "c".to_string()]);
+
+    let mut resized = previous.clone();
+    resized.neurons[0].num_inputs = 3;
+    resized.neurons[0].command = vec!["neurox".to_string()];
+    let plan = ReconcilePlan::new(&resized, Some(&previous), &running);
+    assert_eq!(plan.restart, vec!["a".to_string()]);
+}
@@ -0,0 +1,465 @@
+// topology.rs
+use crate::activation;
+use crate::database::NeuronDb;
+use crate::health;
+use crate::input_schema::InputSchema;
+use crate::neuron::{Neuron, NeuronError, ResizePolicy, SharedNeuron};
+use crate::neuron_metrics;
+use crate::process_manager::RestartConfig;
+use crate::proto::neuron_service_server::NeuronServiceServer;
+use crate::registry;
+use crate::reporter::Reporter;
+use crate::synapse::{DownstreamSynapse, SynapseConfig, UpstreamSynapse};
+use crate::weight_init;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tonic::transport::Server;
+
+const KNOWN_EXTENSIONS: &[&str] = &["eye", "webhook", "messenger_in", "messenger_out"];
+
+#[derive(Error, Debug)]
+pub enum TopologyError {
+    #[error("Failed to read topology file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to parse topology: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Neuron {0} is declared more than once")]
+    DuplicateNeuron(String),
+    #[error("Connection {from} -> {to} references unknown neuron {missing}")]
+    DanglingReference {
+        from: String,
+        to: String,
+        missing: String,
+    },
+    #[error("Connection {from} -> {to} targets slot {slot}, but {to} has {num_inputs} inputs")]
+    SlotOutOfRange {
+        from: String,
+        to: String,
+        slot: usize,
+        num_inputs: usize,
+    },
+    #[error("Slot {slot} of neuron {to} is fed by more than one connection")]
+    DuplicateSlot { to: String, slot: usize },
+    #[error("Neuron {id} has {num_inputs} inputs, but {connected} connections feed it")]
+    UnfedInputs {
+        id: String,
+        num_inputs: usize,
+        connected: usize,
+    },
+    #[error("Connections form a cycle through neuron {0}")]
+    Cycle(String),
+    #[error("Neuron {id} has unknown {kind} {name}")]
+    Unknown {
+        id: String,
+        kind: &'static str,
+        name: String,
+    },
+    #[error("Invalid listen address for neuron {0}: {1}")]
+    InvalidAddr(String, String),
+    #[error("Failed to start neuron {0}: {1}")]
+    Neuron(String, NeuronError),
+}
+
+fn default_activation() -> String {
+    "relu".to_string()
+}
+
+fn default_initializer() -> String {
+    "xavier_uniform".to_string()
+}
+
+/// One neuron of the network. Neurons with a `listen_addr` are instantiated by
+/// the supervisor itself, in-process or as `command`, and restarted according
+/// to `restart`; the rest are expected to be running at `endpoint`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NeuronSpec {
+    pub id: String,
+    pub num_inputs: usize,
+    #[serde(default = "default_activation")]
+    pub activation: String,
+    #[serde(default = "default_initializer")]
+    pub initializer: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    pub endpoint: String,
+    #[serde(default)]
+    pub listen_addr: Option<String>,
+    /// Runs a managed neuron as this child process instead of in-process.
+    #[serde(default)]
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub restart: RestartConfig,
+}
+
+/// Feeds the output of neuron `from` into input `slot` of neuron `to`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionSpec {
+    pub from: String,
+    pub to: String,
+    pub slot: usize,
+}
+
+/// A network of neurons and their connections, e.g.
+///
+/// ```toml
+/// [[neurons]]
+/// id = "n1"
+/// num_inputs = 2
+/// endpoint = "http://[::1]:50061"
+/// listen_addr = "[::1]:50061"
+///
+/// [[connections]]
+/// from = "n1"
+/// to = "n2"
+/// slot = 0
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Topology {
+    #[serde(default)]
+    pub neurons: Vec<NeuronSpec>,
+    #[serde(default)]
+    pub connections: Vec<ConnectionSpec>,
+}
+
+impl Topology {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TopologyError> {
+        let contents = std::fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, TopologyError> {
+        let topology: Topology = toml::from_str(contents)?;
+        topology.validate()?;
+        Ok(topology)
+    }
+
+    pub fn validate(&self) -> Result<(), TopologyError> {
+        let mut neurons = HashMap::new();
+        for spec in &self.neurons {
+            if neurons.insert(spec.id.as_str(), spec).is_some() {
+                return Err(TopologyError::DuplicateNeuron(spec.id.clone()));
+            }
+            if activation::from_name(&spec.activation).is_none() {
+                return Err(TopologyError::Unknown {
+                    id: spec.id.clone(),
+                    kind: "activation",
+                    name: spec.activation.clone(),
+                });
+            }
+            if weight_init::from_name(&spec.initializer).is_none() {
+                return Err(TopologyError::Unknown {
+                    id: spec.id.clone(),
+                    kind: "initializer",
+                    name: spec.initializer.clone(),
+                });
+            }
+            if let Some(extension) = spec
+                .extensions
+                .iter()
+                .find(|extension| !KNOWN_EXTENSIONS.contains(&extension.as_str()))
+            {
+                return Err(TopologyError::Unknown {
+                    id: spec.id.clone(),
+                    kind: "extension",
+                    name: extension.clone(),
+                });
+            }
+        }
+
+        let mut slots = HashSet::new();
+        for connection in &self.connections {
+            for id in &[&connection.from, &connection.to] {
+                if !neurons.contains_key(id.as_str()) {
+                    return Err(TopologyError::DanglingReference {
+                        from: connection.from.clone(),
+                        to: connection.to.clone(),
+                        missing: id.to_string(),
+                    });
+                }
+            }
+            let num_inputs = neurons[connection.to.as_str()].num_inputs;
+            if connection.slot >= num_inputs {
+                return Err(TopologyError::SlotOutOfRange {
+                    from: connection.from.clone(),
+                    to: connection.to.clone(),
+                    slot: connection.slot,
+                    num_inputs,
+                });
+            }
+            if !slots.insert((connection.to.as_str(), connection.slot)) {
+                return Err(TopologyError::DuplicateSlot {
+                    to: connection.to.clone(),
+                    slot: connection.slot,
+                });
+            }
+        }
+
+        self.check_acyclic()?;
+        self.check_fed()
+    }
+
+    /// Rejects neurons fed by connections on only some of their inputs: the
+    /// rest would silently read 0.0 in every wave. Neurons without upstream
+    /// connections take their inputs from clients instead.
+    fn check_fed(&self) -> Result<(), TopologyError> {
+        let mut connected: HashMap<&str, usize> = HashMap::new();
+        for connection in &self.connections {
+            *connected.entry(connection.to.as_str()).or_default() += 1;
+        }
+        for spec in &self.neurons {
+            match connected.get(spec.id.as_str()) {
+                Some(&connected) if connected != spec.num_inputs => {
+                    return Err(TopologyError::UnfedInputs {
+                        id: spec.id.clone(),
+                        num_inputs: spec.num_inputs,
+                        connected,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects cycles: a neuron waits for all of its upstream inputs before
+    /// firing, so a cycle would never complete a wave.
+    fn check_acyclic(&self) -> Result<(), TopologyError> {
+        let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+        for connection in &self.connections {
+            edges
+                .entry(connection.from.as_str())
+                .or_default()
+                .push(connection.to.as_str());
+        }
+
+        // 0 = unvisited, 1 = on the current path, 2 = done.
+        let mut marks: HashMap<&str, u8> = HashMap::new();
+        for spec in &self.neurons {
+            let mut stack = vec![(spec.id.as_str(), 0)];
+            while let Some((id, next_edge)) = stack.pop() {
+                if next_edge == 0 {
+                    match marks.get(id) {
+                        Some(2) => continue,
+                        Some(1) => return Err(TopologyError::Cycle(id.to_string())),
+                        _ => {
+                            marks.insert(id, 1);
+                        }
+                    }
+                }
+                let targets = edges.get(id).map(Vec::as_slice).unwrap_or(&[]);
+                if next_edge < targets.len() {
+                    stack.push((id, next_edge + 1));
+                    let target = targets[next_edge];
+                    if marks.get(target) == Some(&1) {
+                        return Err(TopologyError::Cycle(target.to_string()));
+                    }
+                    stack.push((target, 0));
+                } else {
+                    marks.insert(id, 2);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn neuron(&self, id: &str) -> Option<&NeuronSpec> {
+        self.neurons.iter().find(|spec| spec.id == id)
+    }
+
+    /// Derives the synapses of neuron `id` from the connection list.
+    pub fn synapse_config(&self, id: &str) -> SynapseConfig {
+        let upstream = self
+            .connections
+            .iter()
+            .filter(|connection| connection.to == id)
+            .map(|connection| UpstreamSynapse {
+                source_id: connection.from.clone(),
+                slot: connection.slot,
+            })
+            .collect();
+        let downstream = self
+            .connections
+            .iter()
+            .filter(|connection| connection.from == id)
+            .filter_map(|connection| {
+                self.neuron(&connection.to).map(|target| DownstreamSynapse {
+                    target_id: target.id.clone(),
+                    endpoint: target.endpoint.clone(),
+                    slot: connection.slot,
+                })
+            })
+            .collect();
+        SynapseConfig {
+            upstream,
+            downstream,
+        }
+    }
+
+    /// Whether neuron `id`, present in both topologies, runs differently under
+    /// this one than under `previous`: its spec or its connections changed.
+    /// Restart settings take effect without restarting the neuron.
+    pub fn requires_restart(&self, previous: &Topology, id: &str) -> bool {
+        let (spec, previous_spec) = match (self.neuron(id), previous.neuron(id)) {
+            (Some(spec), Some(previous_spec)) => (spec, previous_spec),
+            _ => return false,
+        };
+        let without_restart = |spec: &NeuronSpec| NeuronSpec {
+            restart: RestartConfig::default(),
+            ..spec.clone()
+        };
+        without_restart(spec) != without_restart(previous_spec)
+            || sorted_synapses(self.synapse_config(id)) != sorted_synapses(previous.synapse_config(id))
+    }
+
+    /// Neurons the supervisor should instantiate itself.
+    pub fn managed_neurons(&self) -> impl Iterator<Item = &NeuronSpec> {
+        self.neurons.iter().filter(|spec| spec.listen_addr.is_some())
+    }
+}
+
+/// Orders synapses so that reordering connections does not count as a change.
+fn sorted_synapses(mut config: SynapseConfig) -> SynapseConfig {
+    config
+        .upstream
+        .sort_by(|a, b| (&a.source_id, a.slot).cmp(&(&b.source_id, b.slot)));
+    config
+        .downstream
+        .sort_by(|a, b| (&a.target_id, a.slot).cmp(&(&b.target_id, b.slot)));
+    config
+}
+
+/// Which managed neurons must be started, stopped or restarted to match a topology.
+#[derive(Debug, Default, PartialEq)]
+pub struct ReconcilePlan {
+    pub start: Vec<String>,
+    pub stop: Vec<String>,
+    /// Neurons kept across the change whose spec or connections changed.
+    pub restart: Vec<String>,
+}
+
+impl ReconcilePlan {
+    /// Plans the move from `previous` to `topology` for the managed neurons in
+    /// `running`.
+    pub fn new(topology: &Topology, previous: Option<&Topology>, running: &HashSet<String>) -> Self {
+        let desired: HashSet<&str> = topology.managed_neurons().map(|spec| spec.id.as_str()).collect();
+        let mut start: Vec<String> = desired
+            .iter()
+            .filter(|id| !running.contains(**id))
+            .map(|id| id.to_string())
+            .collect();
+        let mut stop: Vec<String> = running
+            .iter()
+            .filter(|id| !desired.contains(id.as_str()))
+            .cloned()
+            .collect();
+        let mut restart: Vec<String> = match previous {
+            Some(previous) => running
+                .iter()
+                .filter(|id| desired.contains(id.as_str()) && topology.requires_restart(previous, id))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+        start.sort();
+        stop.sort();
+        restart.sort();
+        Self { start, stop, restart }
+    }
+}
+
+/// An in-process neuron started by `spawn_neuron`. `server` serves it and
+/// keeps it registered until aborted; shut `neuron` down first to drain it.
+pub struct NeuronTask {
+    pub neuron: Arc<Neuron>,
+    pub server: JoinHandle<()>,
+}
+
+/// Instantiates `spec` in this process, wired to its synapses, and serves it
+/// on its `listen_addr`. The neuron keeps its database under `data_dir` and
+/// reports to the supervisor at `supervisor_endpoint`.
+pub fn spawn_neuron(
+    topology: &Topology,
+    spec: &NeuronSpec,
+    supervisor_endpoint: &str,
+    data_dir: &Path,
+) -> Result<NeuronTask, TopologyError> {
+    let addr = spec
+        .listen_addr
+        .as_deref()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|e: std::net::AddrParseError| TopologyError::InvalidAddr(spec.id.clone(), e.to_string()))?;
+    let activation = activation::from_name(&spec.activation).expect("Activation validated with topology");
+    let initializer = weight_init::from_name(&spec.initializer).expect("Initializer validated with topology");
+    if !spec.extensions.is_empty() {
+        log::warn!(
+            "Neuron {} declares extensions {:?}; in-process neurons run without extension servers",
+            spec.id,
+            spec.extensions
+        );
+    }
+
+    let neuron = NeuronDb::new(data_dir.join(&spec.id))
+        .map_err(NeuronError::from)
+        .and_then(|db| {
+            Neuron::open_with_db(
+                spec.id.clone(),
+                db,
+                InputSchema::fixed(spec.num_inputs),
+                activation,
+                initializer.as_ref(),
+                ResizePolicy::Refuse,
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+        })
+        .and_then(|neuron| neuron.with_synapses(topology.synapse_config(&spec.id)))
+        .map_err(|e| TopologyError::Neuron(spec.id.clone(), e))?
+        .with_reporter(Reporter::new(supervisor_endpoint.to_string()));
+
+    let (registered_sender, registered) = watch::channel(false);
+    // Like readiness, the registration runs inside the server task so that
+    // stopping the neuron also stops its heartbeats.
+    let registration = registry::maintain_registration(
+        supervisor_endpoint.to_string(),
+        neuron.registration(spec.endpoint.clone()),
+        registered_sender,
+    );
+
+    let neuron = Arc::new(neuron);
+    Neuron::spawn_metrics_reporting(&neuron, neuron_metrics::DEFAULT_REPORT_INTERVAL);
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    let ready_neuron = neuron.clone();
+    // Runs inside the server task so that stopping the neuron also drops it.
+    let readiness = health::report_readiness::<NeuronServiceServer<SharedNeuron>, _>(health_reporter, move || {
+        ready_neuron.is_ready() && *registered.borrow()
+    });
+
+    let id = spec.id.clone();
+    let serving_neuron = neuron.clone();
+    let server = tokio::spawn(async move {
+        let server = Server::builder()
+            .add_service(health_service)
+            .add_service(health::reflection_service())
+            .add_service(NeuronServiceServer::new(SharedNeuron::new(serving_neuron)))
+            .serve(addr);
+        tokio::select! {
+            result = server => {
+                if let Err(e) = result {
+                    log::error!("Neuron {} server error: {}", id, e);
+                }
+            }
+            _ = readiness => {}
+            _ = registration => {}
+        }
+    });
+    Ok(NeuronTask { neuron, server })
+}
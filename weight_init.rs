@@ -1,5 +1,7 @@
 
 // weight_init.rs
+use rand::Rng;
+
 pub trait WeightInitializer {
     fn initialize(&self, weights: &mut [f32]);
     }
@@ -16,4 +18,10 @@ pub trait WeightInitializer {
     }
     }
     }
-    
\ No newline at end of file
+
+pub fn from_name(name: &str) -> Option<Box<dyn WeightInitializer>> {
+    match name {
+        "xavier_uniform" => Some(Box::new(XavierUniform)),
+        _ => None,
+    }
+}